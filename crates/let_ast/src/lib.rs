@@ -0,0 +1,180 @@
+use std::ops::Range;
+
+/// Byte range of source file covered by node.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Span from start of `self` to end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
+
+    pub fn range(self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Span::new(range.start, range.end)
+    }
+}
+
+/// Operator padded by spaces, like `b"+  "`.
+pub type Operator = [u8; 3];
+
+/// Identifier as written in source, may be dotted like `module.name`
+/// or `object.field`, its meaning is resolved by code generator.
+#[derive(Clone, Debug)]
+pub struct Name {
+    pub value: Box<[u8]>,
+    pub span: Span,
+}
+
+/// Parameter with optional type annotation, like `a: int`.
+#[derive(Debug)]
+pub struct Parameter {
+    pub name: Name,
+    pub annotation: Option<Name>,
+}
+
+#[derive(Debug)]
+pub struct Function {
+    pub name: Name,
+    pub parameters: Vec<Parameter>,
+    /// Annotation of returned value, like `-> int`.
+    pub returns: Option<Name>,
+    pub body: Vec<Expression>,
+    pub span: Span,
+}
+
+/*
+   class Name(Parent)
+       fn init(self ...) ... end
+       fn method(self ...) ... end
+   end
+*/
+#[derive(Debug)]
+pub struct Class {
+    pub name: Name,
+    pub parent: Option<Name>,
+    pub methods: Vec<Function>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub enum Item {
+    Function(Function),
+    Class(Class),
+    /// Global code of module.
+    Code(Expression),
+}
+
+/// Parsed source file.
+#[derive(Debug, Default)]
+pub struct Module {
+    pub items: Vec<Item>,
+}
+
+/// Branch of `if` with its condition, span starts at `if` or `elif`
+/// and ends with the body.
+#[derive(Debug)]
+pub struct Branch {
+    pub condition: Expression,
+    pub body: Vec<Expression>,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+#[derive(Debug)]
+pub enum ExpressionKind {
+    /// Non-negative integer as big-endian bytes, it may not fit
+    /// into `i64`.
+    Integer(Box<[u8]>),
+    Real(f64),
+    String(Box<[u8]>),
+    /// Interpolated string, parts are strings and expressions.
+    Interpolation(Vec<Expression>),
+    List(Vec<Expression>),
+    Identifier(Name),
+    Paren(Box<Expression>),
+    Call {
+        callee: Box<Expression>,
+        arguments: Vec<Expression>,
+    },
+    /// `super.method(...)`
+    Super {
+        method: Name,
+        arguments: Vec<Expression>,
+    },
+    /// `object.name` or `object?.name`.
+    Field {
+        object: Box<Expression>,
+        name: Name,
+        optional: bool,
+    },
+    /// `object[index]` or `object?[index]`.
+    Index {
+        object: Box<Expression>,
+        index: Box<Expression>,
+        optional: bool,
+    },
+    Assign {
+        target: Box<Expression>,
+        value: Box<Expression>,
+    },
+    Unary {
+        operator: Operator,
+        operand: Box<Expression>,
+    },
+    Binary {
+        operator: Operator,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    /// Comparison chain like `0 <= i < n`.
+    Comparison {
+        first: Box<Expression>,
+        rest: Vec<(Operator, Expression)>,
+    },
+    Range {
+        start: Option<Box<Expression>>,
+        end: Option<Box<Expression>>,
+        inclusive: bool,
+    },
+    If {
+        branches: Vec<Branch>,
+        otherwise: Option<Vec<Expression>>,
+    },
+    While {
+        condition: Box<Expression>,
+        body: Vec<Expression>,
+    },
+    Let {
+        name: Name,
+        annotation: Option<Name>,
+        constant: bool,
+        value: Box<Expression>,
+    },
+    /// Function declared inside other function.
+    Function(Box<Function>),
+}
@@ -0,0 +1,6 @@
+/// Names of functions that are implemented by VM.
+const NAMES: [&[u8]; 1] = [b"format"];
+
+pub fn is_builtin(name: &[u8]) -> bool {
+    NAMES.binary_search(&name).is_ok()
+}
@@ -0,0 +1,50 @@
+/// How a failed check is reported.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    Warning,
+    Error,
+}
+
+impl From<Level> for let_result::Severity {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Warning => let_result::Severity::Warning,
+            Level::Error => let_result::Severity::Error,
+        }
+    }
+}
+
+/// Levels of variable binding checks.
+#[derive(Clone, Copy)]
+pub struct Checks {
+    /// Assignment to a `const` binding.
+    pub const_assign: Level,
+    /// `let` or `const` with a name already declared in the same block.
+    pub redeclaration: Level,
+    /// `let` or `const` with a name of function parameter.
+    pub shadow_parameter: Level,
+}
+
+impl Default for Checks {
+    fn default() -> Self {
+        Self {
+            const_assign: Level::Error,
+            redeclaration: Level::Warning,
+            shadow_parameter: Level::Warning,
+        }
+    }
+}
+
+impl Checks {
+    /// Sets level of check by its command line name.
+    /// Returns false if there is no such check.
+    pub fn set(&mut self, name: &str, level: Level) -> bool {
+        match name {
+            "const-assign" => self.const_assign = level,
+            "redeclaration" => self.redeclaration = level,
+            "shadow-parameter" => self.shadow_parameter = level,
+            _ => return false,
+        }
+        true
+    }
+}
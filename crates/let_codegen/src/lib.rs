@@ -0,0 +1,1270 @@
+mod builtins;
+mod checks;
+mod types;
+
+use std::collections::HashMap;
+
+pub use checks::{Checks, Level};
+use let_ast::{self as ast, Expression, ExpressionKind, Name, Operator, Span};
+pub use let_result::Result;
+use let_result::{codes, Diagnostic};
+pub use types::{TypeChecker, Types};
+
+/// Reference to local function that is not declared yet.
+struct Reference {
+    label: u32,
+    depth_address: u32,
+    level: usize,
+    span: Span,
+}
+
+#[derive(Clone)]
+struct Local {
+    id: u32,
+    constant: bool,
+    parameter: bool,
+    /// Type checked at run time when variable is assigned.
+    annotation: Option<Box<[u8]>>,
+    span: Span,
+}
+
+struct Block {
+    locals: HashMap<Box<[u8]>, Local>,
+    functions: HashMap<Box<[u8]>, u32>,
+    references: HashMap<Box<[u8]>, Vec<Reference>>,
+}
+
+impl Block {
+    fn new() -> Self {
+        Self {
+            locals: HashMap::new(),
+            functions: HashMap::new(),
+            references: HashMap::new(),
+        }
+    }
+
+    /// Declares variable, redeclared variable keeps its slot.
+    /// Returns true if variable is new.
+    fn var(&mut self, name: &[u8], mut local: Local) -> (u32, bool) {
+        if let Some(old) = self.locals.get_mut(name) {
+            local.id = old.id;
+            *old = local;
+            (old.id, false)
+        } else {
+            let id = local.id;
+            self.locals
+                .insert(Vec::from(name).into_boxed_slice(), local);
+            (id, true)
+        }
+    }
+
+    fn get(&self, name: &[u8]) -> Option<&Local> {
+        self.locals.get(name)
+    }
+
+    fn len(&self) -> usize {
+        self.locals.len()
+    }
+
+    fn forward(&mut self, references: HashMap<Box<[u8]>, Vec<Reference>>) {
+        for (name, references) in references {
+            self.references.entry(name).or_default().extend(references);
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Variable {
+    Local(u32),
+    /// Variable of enclosing function, depth and index.
+    Outer(u8, u8),
+}
+
+enum Symbol {
+    Variable(Variable, Local),
+    /// Local function label and depth of its enclosing function.
+    Function(u32, u32),
+}
+
+struct Function {
+    /// Label of function, local functions are named after enclosing one.
+    name: Box<[u8]>,
+    blocks: Vec<Block>,
+    local_counter: u32,
+    stack_size: u32,
+    parameters: u32,
+    nested: bool,
+}
+
+impl Function {
+    fn new(name: Box<[u8]>, nested: bool) -> Self {
+        Self {
+            name,
+            blocks: vec![Block::new()],
+            local_counter: 0,
+            stack_size: 0,
+            parameters: 0,
+            nested,
+        }
+    }
+
+    fn push(&mut self) {
+        self.blocks.push(Block::new());
+    }
+
+    fn pop(&mut self) {
+        let block = self.blocks.pop().unwrap();
+        self.local_counter -= block.len() as u32;
+        self.block().forward(block.references);
+    }
+
+    fn block(&mut self) -> &mut Block {
+        debug_assert!(!self.blocks.is_empty());
+        self.blocks.last_mut().unwrap()
+    }
+
+    fn var(&mut self, name: &[u8], constant: bool, parameter: bool, span: Span) -> u32 {
+        let local = Local {
+            id: self.local_counter,
+            constant,
+            parameter,
+            annotation: None,
+            span,
+        };
+        let (id, new) = self.block().var(name, local);
+        if new {
+            self.local_counter += 1;
+            if self.local_counter > self.stack_size {
+                self.stack_size = self.local_counter;
+            }
+        }
+        id
+    }
+
+    fn parameter(&self, name: &[u8]) -> Option<&Local> {
+        self.blocks[0].get(name).filter(|local| local.parameter)
+    }
+
+    /// Names and locations of local functions that are referenced,
+    /// but not declared, in source order.
+    fn unknown_references(&self) -> Vec<(String, Span)> {
+        let mut unknown: Vec<(String, Span)> = self.blocks[0]
+            .references
+            .iter()
+            .map(|(name, references)| {
+                (
+                    String::from_utf8_lossy(name).into_owned(),
+                    references[0].span,
+                )
+            })
+            .collect();
+        unknown.sort_by_key(|(_, span)| span.start);
+        unknown
+    }
+}
+
+/// Variable holding object and names of its fields, like `object.a.b`.
+type Member<'n> = (Variable, Vec<&'n [u8]>);
+
+/// Full name of module member, like `module.name`.
+fn qualify(module: &[u8], name: &[u8]) -> Box<[u8]> {
+    let mut full_name = Vec::with_capacity(module.len() + name.len() + 1);
+    full_name.extend(module);
+    full_name.push(b'.');
+    full_name.extend(name);
+    full_name.into_boxed_slice()
+}
+
+/// Full name of parent class, parent from the same module may be
+/// unqualified.
+fn parent_name(module: &[u8], class: &ast::Class) -> Option<Box<[u8]>> {
+    class.parent.as_ref().map(|parent| {
+        if parent.value.contains(&b'.') {
+            parent.value.clone()
+        } else {
+            qualify(module, &parent.value)
+        }
+    })
+}
+
+/// `init` called by constructor of a class.
+enum Init<'m> {
+    Method(&'m ast::Function),
+    /// Neither class nor its ancestors declare `init`.
+    Missing,
+    /// `init` may be declared by ancestor from other module.
+    Inherited(Box<[u8]>),
+}
+
+/// Arguments of constructor, it takes parameters of `init` without `self`.
+enum Constructor {
+    Arguments(u32),
+    /// Constructor of ancestor from other module, its arguments are
+    /// known from the emitter.
+    Inherited(Box<[u8]>),
+}
+
+/// Finds nearest `init` of class, declared by class or its ancestors
+/// from the same module.
+fn find_init<'m>(module_name: &[u8], module: &'m ast::Module, class: &'m ast::Class) -> Init<'m> {
+    let classes: HashMap<Box<[u8]>, &ast::Class> = module
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ast::Item::Class(class) => Some((qualify(module_name, &class.name.value), class)),
+            _ => None,
+        })
+        .collect();
+    let mut current = class;
+    // Inheritance cycles are reported by the VM.
+    for _ in 0..=classes.len() {
+        let init = current
+            .methods
+            .iter()
+            .find(|method| method.name.value.as_ref() == b"init");
+        if let Some(init) = init {
+            return Init::Method(init);
+        }
+        match parent_name(module_name, current) {
+            Some(parent) => match classes.get(&parent) {
+                Some(class) => current = class,
+                None => return Init::Inherited(parent),
+            },
+            None => break,
+        }
+    }
+    Init::Missing
+}
+
+/// Walks syntax tree of a module and drives the emitter.
+///
+/// Generator doesn't stop at the first error. Broken statement or item
+/// is reported and skipped, code of module with errors must not be used.
+pub struct Generator<'a> {
+    emitter: &'a mut let_emitter::Emitter,
+    lable_id: usize,
+    functions: Vec<Function>,
+    module: &'a [u8],
+    checks: Checks,
+    types: Types,
+    /// Warnings and errors in order of generation.
+    diagnostics: Vec<Diagnostic>,
+    /// Full name of class which methods are generated.
+    class: Option<Box<[u8]>>,
+    /// Location of node being generated.
+    span: Span,
+    /// Constructors of the module classes by full name.
+    constructors: HashMap<Box<[u8]>, Constructor>,
+}
+
+impl<'a> Generator<'a> {
+    pub fn new(module: &'a [u8], emitter: &'a mut let_emitter::Emitter) -> Self {
+        Self {
+            emitter,
+            lable_id: 0,
+            functions: vec![Function::new(Vec::from(module).into_boxed_slice(), false)],
+            module,
+            checks: Checks::default(),
+            types: Types::default(),
+            diagnostics: Vec::new(),
+            class: None,
+            span: Span::default(),
+            constructors: HashMap::new(),
+        }
+    }
+
+    pub fn with_checks(mut self, checks: Checks) -> Self {
+        self.checks = checks;
+        self
+    }
+
+    /// Values verified by `TypeChecker` are not checked at run time.
+    pub fn with_types(mut self, types: Types) -> Self {
+        self.types = types;
+        self
+    }
+
+    fn get_lable_id(&mut self) -> usize {
+        let result = self.lable_id;
+        self.lable_id += 1;
+        result
+    }
+
+    /// Reports error located at `span`.
+    fn fail<T>(&self, code: &'static str, span: Span, message: String) -> let_result::Result<T> {
+        Err(Diagnostic::error(code, message)
+            .with_range(span.range())
+            .into())
+    }
+
+    /// Finds variable or local function visible from current function,
+    /// including variables of enclosing functions.
+    fn find_symbol(&self, name: &[u8]) -> let_result::Result<Option<Symbol>> {
+        let current = self.functions.len() - 1;
+        for (level, function) in self.functions.iter().enumerate().rev() {
+            let depth = (current - level) as u32;
+            for block in function.blocks.iter().rev() {
+                if let Some(local) = block.get(name) {
+                    if depth == 0 {
+                        return Ok(Some(Symbol::Variable(
+                            Variable::Local(local.id),
+                            local.clone(),
+                        )));
+                    }
+                    if depth > u8::MAX as u32 || local.id > u8::MAX as u32 {
+                        return let_result::raise!(
+                            codes::CAPTURE_TOO_FAR,
+                            "Variable is too far to be captured."
+                        );
+                    }
+                    return Ok(Some(Symbol::Variable(
+                        Variable::Outer(depth as u8, local.id as u8),
+                        local.clone(),
+                    )));
+                }
+                if let Some(&label) = block.functions.get(name) {
+                    return Ok(Some(Symbol::Function(label, depth)));
+                }
+            }
+            if !function.nested {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    fn load(&mut self, variable: Variable) {
+        match variable {
+            Variable::Local(index) => self.emitter.load(index),
+            Variable::Outer(depth, index) => self.emitter.load_outer(depth, index),
+        }
+    }
+
+    fn store(&mut self, variable: Variable) {
+        match variable {
+            Variable::Local(index) => self.emitter.store(index),
+            Variable::Outer(depth, index) => self.emitter.store_outer(depth, index),
+        }
+    }
+
+    fn closure(&mut self, label: u32, depth: u32) -> let_result::Result<u32> {
+        if depth > u8::MAX as u32 {
+            return let_result::raise!(
+                codes::CAPTURE_TOO_FAR,
+                "Function is too far to be captured."
+            );
+        }
+        self.emitter.pointer_label(label)?;
+        self.emitter.closure(depth as u8)
+    }
+
+    /// Local function can be declared later in the same or enclosing block.
+    fn forward_reference(&mut self, name: &Name) -> let_result::Result {
+        let label = self.get_lable_id() as u32;
+        let depth_address = self.closure(label, 0)?;
+        let level = self.functions.len() - 1;
+        self.functions
+            .last_mut()
+            .unwrap()
+            .block()
+            .references
+            .entry(name.value.clone())
+            .or_default()
+            .push(Reference {
+                label,
+                depth_address,
+                level,
+                span: name.span,
+            });
+        Ok(())
+    }
+
+    fn arguments(&mut self, arguments: &[Expression]) -> let_result::Result<u8> {
+        for argument in arguments {
+            self.expression(argument)?;
+        }
+        Ok(arguments.len() as u8)
+    }
+
+    /// Interpolated string is compiled as concatenation of its parts.
+    fn interpolation(&mut self, parts: &[Expression], span: Span) -> let_result::Result {
+        for part in parts {
+            self.expression(part)?;
+        }
+        if parts.len() > u8::MAX as usize {
+            return self.fail(
+                codes::TOO_MANY_PARTS,
+                span,
+                "Reached maximum interpolated string parts number".to_string(),
+            );
+        }
+        self.emitter.concat(parts.len() as u8)
+    }
+
+    fn list(&mut self, items: &[Expression]) -> let_result::Result {
+        self.emitter.list();
+        for item in items {
+            self.expression(item)?;
+            self.emitter.binary(*b"+  ")?;
+        }
+        Ok(())
+    }
+
+    /// Splits member access like `object.field` written as one identifier,
+    /// if `object` is a variable. Returns the variable and field names.
+    fn member<'n>(&mut self, name: &'n Name) -> let_result::Result<Option<Member<'n>>> {
+        let dot = match name.value.iter().position(|&c| c == b'.') {
+            Some(dot) => dot,
+            None => return Ok(None),
+        };
+        let variable = match self.find_symbol(&name.value[..dot])? {
+            Some(Symbol::Variable(variable, _)) => variable,
+            _ => return Ok(None),
+        };
+        let fields: Vec<&[u8]> = name.value[dot + 1..].split(|&c| c == b'.').collect();
+        if fields.iter().any(|field| field.is_empty()) {
+            return self.fail(
+                codes::EXPECTED_TOKEN,
+                name.span,
+                "Expected field name.".to_string(),
+            );
+        }
+        Ok(Some((variable, fields)))
+    }
+
+    /// Loads object of member access and gets all fields but the last one.
+    fn object_fields<'n>(
+        &mut self,
+        variable: Variable,
+        mut fields: Vec<&'n [u8]>,
+    ) -> let_result::Result<&'n [u8]> {
+        self.load(variable);
+        let last = fields.pop().unwrap();
+        for field in fields {
+            self.emitter.get_field(field)?;
+        }
+        Ok(last)
+    }
+
+    fn identifier(&mut self, name: &Name) -> let_result::Result {
+        if let Some((variable, fields)) = self.member(name)? {
+            let last = self.object_fields(variable, fields)?;
+            return self.emitter.get_field(last);
+        }
+        match self.find_symbol(&name.value)? {
+            Some(Symbol::Variable(variable, _)) => {
+                self.load(variable);
+                Ok(())
+            }
+            Some(Symbol::Function(label, depth)) => {
+                self.closure(label, depth)?;
+                Ok(())
+            }
+            None if builtins::is_builtin(&name.value) => self.fail(
+                codes::BUILTIN_NOT_CALLED,
+                name.span,
+                "Expected '('.".to_string(),
+            ),
+            None if !name.value.contains(&b'.') => self.forward_reference(name),
+            None => self.emitter.pointer(&name.value, name.span.range()),
+        }
+    }
+
+    fn call(&mut self, callee: &Expression, arguments: &[Expression]) -> let_result::Result {
+        let name = match &callee.kind {
+            ExpressionKind::Identifier(name) => name,
+            _ => {
+                self.expression(callee)?;
+                let arguments = self.arguments(arguments)?;
+                return self.emitter.call(arguments);
+            }
+        };
+
+        if let Some((variable, fields)) = self.member(name)? {
+            let method = self.object_fields(variable, fields)?;
+            return self.method(method, arguments, callee.span);
+        }
+        match self.find_symbol(&name.value)? {
+            Some(Symbol::Variable(variable, _)) => self.load(variable),
+            Some(Symbol::Function(label, depth)) => {
+                self.closure(label, depth)?;
+            }
+            None if builtins::is_builtin(&name.value) => {
+                let arguments = self.arguments(arguments)?;
+                return self.emitter.builtin(&name.value, arguments);
+            }
+            None if !name.value.contains(&b'.') => self.forward_reference(name)?,
+            None => self.emitter.call_pointer(
+                &name.value,
+                arguments.len() as u8,
+                callee.span.range(),
+            )?,
+        }
+        let arguments = self.arguments(arguments)?;
+        self.emitter.call(arguments)
+    }
+
+    /// Method is called with the object on the stack as the first argument.
+    fn method(&mut self, name: &[u8], arguments: &[Expression], span: Span) -> let_result::Result {
+        if arguments.len() == u8::MAX as usize {
+            return self.fail(
+                codes::TOO_MANY_ARGUMENTS,
+                span,
+                "Reached maximum function argumens number".to_string(),
+            );
+        }
+        self.emitter.void();
+        self.emitter.swap();
+        let arguments = self.arguments(arguments)?;
+        self.emitter.invoke(name, arguments + 1)
+    }
+
+    /// Calls method of parent class, method is looked up by the VM
+    /// starting from parent of the class being generated.
+    fn super_call(
+        &mut self,
+        method: &Name,
+        arguments: &[Expression],
+        span: Span,
+    ) -> let_result::Result {
+        let class = match &self.class {
+            Some(class) => class.clone(),
+            None => {
+                return self.fail(
+                    codes::SUPER_OUTSIDE_CLASS,
+                    span,
+                    "\"super\" is used outside of class.".to_string(),
+                )
+            }
+        };
+        if arguments.len() == u8::MAX as usize {
+            return self.fail(
+                codes::TOO_MANY_ARGUMENTS,
+                span,
+                "Reached maximum function argumens number".to_string(),
+            );
+        }
+        self.emitter.string(&class)?;
+        self.object(b"self", span)?;
+        let arguments = self.arguments(arguments)?;
+        self.emitter.invoke(&method.value, arguments + 1)
+    }
+
+    /// Loads variable holding object.
+    fn object(&mut self, name: &[u8], span: Span) -> let_result::Result {
+        match self.find_symbol(name)? {
+            Some(Symbol::Variable(variable, _)) => {
+                self.load(variable);
+                Ok(())
+            }
+            _ => self.fail(
+                codes::UNKNOWN_VARIABLE,
+                span,
+                format!("Unknown variable \"{}\".", String::from_utf8_lossy(name)),
+            ),
+        }
+    }
+
+    /// Generates postfix chain like `a?.b.c(x)?[i]`. Optional links jump
+    /// to the end of the whole chain if the value is void, so the rest
+    /// of the chain is skipped and results in void.
+    fn chain(&mut self, expression: &Expression) -> let_result::Result {
+        let mut end_id = None;
+        self.link(expression, &mut end_id)?;
+        if let Some(id) = end_id {
+            self.emitter.label(id)?;
+        }
+        Ok(())
+    }
+
+    fn link(&mut self, expression: &Expression, end_id: &mut Option<u32>) -> let_result::Result {
+        self.span = expression.span;
+        match &expression.kind {
+            ExpressionKind::Field {
+                object,
+                name,
+                optional,
+            } => {
+                self.link_object(object, *optional, end_id)?;
+                self.emitter.get_field(&name.value)
+            }
+            ExpressionKind::Index {
+                object,
+                index,
+                optional,
+            } => {
+                self.link_object(object, *optional, end_id)?;
+                self.expression(index)?;
+                self.emitter.index_get();
+                Ok(())
+            }
+            ExpressionKind::Call { callee, arguments } => match &callee.kind {
+                ExpressionKind::Field {
+                    object,
+                    name,
+                    optional,
+                } => {
+                    self.link_object(object, *optional, end_id)?;
+                    self.method(&name.value, arguments, expression.span)
+                }
+                _ => self.call(callee, arguments),
+            },
+            _ => self.expression(expression),
+        }
+    }
+
+    /// Generates object of chain link, optional link is skipped if
+    /// the object is void.
+    fn link_object(
+        &mut self,
+        object: &Expression,
+        optional: bool,
+        end_id: &mut Option<u32>,
+    ) -> let_result::Result {
+        self.link(object, end_id)?;
+        if optional {
+            let id = *end_id.get_or_insert(self.get_lable_id() as u32);
+            self.emitter.jump_void(id)?;
+        }
+        Ok(())
+    }
+
+    fn assign(&mut self, target: &Expression, value: &Expression) -> let_result::Result {
+        let mut end_id = None;
+        match &target.kind {
+            ExpressionKind::Identifier(name) => {
+                if let Some((variable, fields)) = self.member(name)? {
+                    let last = self.object_fields(variable, fields)?;
+                    self.expression(value)?;
+                    return self.emitter.set_field(last);
+                }
+                match self.find_symbol(&name.value)? {
+                    Some(Symbol::Variable(variable, local)) => {
+                        if local.constant {
+                            let name = String::from_utf8_lossy(&name.value);
+                            let diagnostic = Diagnostic::error(
+                                codes::CONST_ASSIGN,
+                                format!("Assignment to constant \"{name}\"."),
+                            )
+                            .with_range(target.span.range())
+                            .with_label(local.span.range(), format!("\"{name}\" is declared here."))
+                            .with_help("Declare it with \"let\" to allow assignment.".to_string());
+                            self.check(self.checks.const_assign, diagnostic);
+                        }
+                        self.expression(value)?;
+                        self.check_type(local.annotation.as_deref(), value.span)?;
+                        self.store(variable);
+                        return Ok(());
+                    }
+                    _ => {
+                        return self.fail(
+                            codes::UNKNOWN_VARIABLE,
+                            name.span,
+                            format!(
+                                "Unknown variable \"{}\".",
+                                String::from_utf8_lossy(&name.value)
+                            ),
+                        )
+                    }
+                }
+            }
+            ExpressionKind::Field {
+                object,
+                name,
+                optional,
+            } => {
+                self.link_object(object, *optional, &mut end_id)?;
+                self.expression(value)?;
+                self.emitter.set_field(&name.value)?;
+            }
+            ExpressionKind::Index {
+                object,
+                index,
+                optional,
+            } => {
+                self.link_object(object, *optional, &mut end_id)?;
+                self.expression(index)?;
+                self.expression(value)?;
+                self.emitter.index_set();
+            }
+            _ => {
+                return self.fail(
+                    codes::INVALID_ASSIGNMENT_TARGET,
+                    target.span,
+                    "Invalid assignment target.".to_string(),
+                )
+            }
+        }
+        if let Some(id) = end_id {
+            self.emitter.label(id)?;
+        }
+        Ok(())
+    }
+
+    fn unary(&mut self, operator: &Operator, operand: &Expression) -> let_result::Result {
+        self.expression(operand)?;
+        match operator {
+            b"-  " => self.emitter.negate(),
+            _ => self.emitter.not(),
+        }
+        Ok(())
+    }
+
+    fn binary(
+        &mut self,
+        operator: Operator,
+        left: &Expression,
+        right: &Expression,
+        span: Span,
+    ) -> let_result::Result {
+        self.expression(left)?;
+        if &operator == b"?? " {
+            // Right operand is evaluated only if left one is void.
+            let end_id = self.get_lable_id() as u32;
+            self.emitter.jump_not_void(end_id)?;
+            self.expression(right)?;
+            return self.emitter.label(end_id);
+        }
+        self.expression(right)?;
+        self.span = span;
+        self.emitter.binary(operator)
+    }
+
+    /*
+       Each middle operand is evaluated once and the chain stops
+       at the first false comparison:
+
+           first
+           operand
+           DUP, ROT      ; Keep right operand for the next comparison.
+           LS
+           JPFK false
+           ...
+           last operand
+           LS
+           JP end
+       false:
+           SWAP, DROP    ; Drop kept operand under false result.
+       end:
+    */
+    fn comparison(
+        &mut self,
+        first: &Expression,
+        rest: &[(Operator, Expression)],
+    ) -> let_result::Result {
+        self.expression(first)?;
+        let false_id = self.get_lable_id() as u32;
+        let end_id = self.get_lable_id() as u32;
+        let ((last_operator, last), middle) = rest.split_last().unwrap();
+        for (operator, operand) in middle {
+            self.expression(operand)?;
+            self.emitter.dup();
+            self.emitter.rotate();
+            self.emitter.binary(*operator)?;
+            self.emitter.jump_false_keep(false_id)?;
+        }
+        self.expression(last)?;
+        self.emitter.binary(*last_operator)?;
+        self.emitter.jump(end_id)?;
+
+        self.emitter.label(false_id)?;
+        self.emitter.swap();
+        self.emitter.drop()?;
+        self.emitter.label(end_id)
+    }
+
+    /// Missing start or end of range is void.
+    fn range(
+        &mut self,
+        start: Option<&Expression>,
+        end: Option<&Expression>,
+        inclusive: bool,
+    ) -> let_result::Result {
+        for bound in [start, end] {
+            match bound {
+                Some(bound) => self.expression(bound)?,
+                None => self.emitter.void(),
+            }
+        }
+        self.emitter
+            .binary(if inclusive { *b"..=" } else { *b".. " })
+    }
+
+    /// Value of block is the value of its last expression,
+    /// empty block results in void.
+    fn block(&mut self, expressions: &[Expression]) -> let_result::Result {
+        if expressions.is_empty() {
+            self.emitter.void();
+        }
+        for (index, expression) in expressions.iter().enumerate() {
+            if index > 0 {
+                self.emitter.drop()?;
+            }
+            // Broken statement is reported and the next one is generated.
+            let depth = self.depth();
+            if let Err(error) = self.expression(expression) {
+                self.report(error);
+                self.recover(depth);
+            }
+        }
+        Ok(())
+    }
+
+    fn scoped_block(&mut self, expressions: &[Expression]) -> let_result::Result {
+        self.enter_block();
+        self.block(expressions)?;
+        self.exit_block();
+        Ok(())
+    }
+
+    fn expression(&mut self, expression: &Expression) -> let_result::Result {
+        self.span = expression.span;
+        match &expression.kind {
+            ExpressionKind::Integer(value) => self.emitter.integer(value),
+            ExpressionKind::Real(value) => self.emitter.real(*value),
+            ExpressionKind::String(value) => self.emitter.string(value),
+            ExpressionKind::Interpolation(parts) => self.interpolation(parts, expression.span),
+            ExpressionKind::List(items) => self.list(items),
+            ExpressionKind::Identifier(name) => self.identifier(name),
+            ExpressionKind::Paren(expression) => self.expression(expression),
+            ExpressionKind::Call { callee, arguments } => match callee.kind {
+                ExpressionKind::Field { .. } => self.chain(expression),
+                _ => self.call(callee, arguments),
+            },
+            ExpressionKind::Super { method, arguments } => {
+                self.super_call(method, arguments, expression.span)
+            }
+            ExpressionKind::Field { .. } | ExpressionKind::Index { .. } => self.chain(expression),
+            ExpressionKind::Assign { target, value } => self.assign(target, value),
+            ExpressionKind::Unary { operator, operand } => self.unary(operator, operand),
+            ExpressionKind::Binary {
+                operator,
+                left,
+                right,
+            } => self.binary(*operator, left, right, expression.span),
+            ExpressionKind::Comparison { first, rest } => self.comparison(first, rest),
+            ExpressionKind::Range {
+                start,
+                end,
+                inclusive,
+            } => self.range(start.as_deref(), end.as_deref(), *inclusive),
+            ExpressionKind::If {
+                branches,
+                otherwise,
+            } => self.p_if(branches, otherwise.as_deref()),
+            ExpressionKind::While { condition, body } => self.p_while(condition, body),
+            ExpressionKind::Let {
+                name,
+                annotation,
+                constant,
+                value,
+            } => self.p_let(name, annotation.as_ref(), *constant, value),
+            ExpressionKind::Function(function) => self.local_function(function),
+        }
+    }
+
+    fn enter_block(&mut self) {
+        self.functions.last_mut().unwrap().push();
+    }
+
+    fn exit_block(&mut self) {
+        self.functions.last_mut().unwrap().pop();
+    }
+
+    /// Reports failed check as warning or error by its level, generation
+    /// goes on in both cases.
+    fn check(&mut self, level: Level, diagnostic: Diagnostic) {
+        self.diagnostics.push(Diagnostic {
+            severity: level.into(),
+            ..diagnostic
+        });
+    }
+
+    /// Collects error located at the node being generated.
+    fn report(&mut self, error: let_result::Error) {
+        let diagnostic = error.locate(self.span.range()).into_diagnostic();
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Closes functions and blocks opened after `depth` was taken,
+    /// an error may leave them open.
+    fn recover(&mut self, (functions, blocks): (usize, usize)) {
+        self.functions.truncate(functions);
+        let function = self.functions.last_mut().unwrap();
+        while function.blocks.len() > blocks {
+            function.pop();
+        }
+    }
+
+    /// Count of open functions and blocks of the current one.
+    fn depth(&self) -> (usize, usize) {
+        (
+            self.functions.len(),
+            self.functions.last().unwrap().blocks.len(),
+        )
+    }
+
+    /// Emits runtime check of annotated value unless it is verified.
+    fn check_type(&mut self, annotation: Option<&[u8]>, value: Span) -> let_result::Result {
+        match annotation {
+            Some(name) if !self.types.is_verified(value) => self.emitter.check_type(name),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets annotation of the last declared variable.
+    fn annotate(&mut self, name: &[u8], annotation: Option<&Name>) -> Option<Box<[u8]>> {
+        let annotation =
+            annotation.map(|annotation| types::type_name(self.module, &annotation.value));
+        let local = self
+            .functions
+            .last_mut()
+            .unwrap()
+            .block()
+            .locals
+            .get_mut(name)
+            .unwrap();
+        local.annotation = annotation.clone();
+        annotation
+    }
+
+    fn add_parameter(&mut self, parameter: &ast::Parameter) -> u32 {
+        let name = &parameter.name;
+        let id = self
+            .functions
+            .last_mut()
+            .unwrap()
+            .var(&name.value, false, true, name.span);
+        self.annotate(&name.value, parameter.annotation.as_ref());
+        id
+    }
+
+    fn add_local(&mut self, name: &Name, constant: bool) -> let_result::Result<u32> {
+        let text = String::from_utf8_lossy(&name.value).into_owned();
+        let function = self.functions.last().unwrap();
+        if let Some(parameter) = function.parameter(&name.value) {
+            let diagnostic = Diagnostic::error(
+                codes::SHADOW_PARAMETER,
+                format!("Variable \"{text}\" shadows parameter."),
+            )
+            .with_range(name.span.range())
+            .with_label(
+                parameter.span.range(),
+                format!("Parameter \"{text}\" is declared here."),
+            );
+            self.check(self.checks.shadow_parameter, diagnostic);
+        } else if let Some(local) = function.blocks.last().unwrap().get(&name.value) {
+            let diagnostic = Diagnostic::error(
+                codes::REDECLARATION,
+                format!("Variable \"{text}\" is already declared in this block."),
+            )
+            .with_range(name.span.range())
+            .with_label(local.span.range(), format!("\"{text}\" is declared here."));
+            self.check(self.checks.redeclaration, diagnostic);
+        }
+        Ok(self
+            .functions
+            .last_mut()
+            .unwrap()
+            .var(&name.value, constant, false, name.span))
+    }
+
+    fn p_let(
+        &mut self,
+        name: &Name,
+        annotation: Option<&Name>,
+        constant: bool,
+        value: &Expression,
+    ) -> let_result::Result {
+        let local_id = self.add_local(name, constant)?;
+        let annotation = self.annotate(&name.value, annotation);
+        self.expression(value)?;
+        self.check_type(annotation.as_deref(), value.span)?;
+        self.emitter.store(local_id);
+        Ok(())
+    }
+
+    /// `if` without `else` results in void when no branch is taken.
+    fn p_if(
+        &mut self,
+        branches: &[ast::Branch],
+        otherwise: Option<&[Expression]>,
+    ) -> let_result::Result {
+        let end_if_id = self.get_lable_id() as u32;
+        for branch in branches {
+            self.expression(&branch.condition)?;
+            let next_id = self.get_lable_id() as u32;
+            self.emitter.jump_false(next_id)?;
+            self.scoped_block(&branch.body)?;
+            self.emitter.jump(end_if_id)?;
+            self.emitter.label(next_id)?;
+        }
+        match otherwise {
+            Some(body) => self.scoped_block(body)?,
+            None => self.emitter.void(),
+        }
+        self.emitter.label(end_if_id)
+    }
+
+    fn p_while(&mut self, condition: &Expression, body: &[Expression]) -> let_result::Result {
+        self.emitter.void();
+
+        let while_start = self.emitter.offset();
+
+        self.expression(condition)?;
+        let end_id = self.get_lable_id() as u32;
+        self.emitter.jump_false(end_id)?;
+
+        self.emitter.drop()?;
+
+        self.scoped_block(body)?;
+
+        self.emitter.jump_to(while_start);
+
+        self.emitter.label(end_id)
+    }
+
+    /// Generates parameters and body of function with already emitted label.
+    fn function_body(
+        &mut self,
+        function: &ast::Function,
+        name: Box<[u8]>,
+        nested: bool,
+    ) -> let_result::Result<Function> {
+        let args_count = function.parameters.len() as u32;
+        let index = self
+            .emitter
+            .function(&name, args_count as u8, function.span.range())?;
+        self.functions.push(Function::new(name, nested));
+
+        for parameter in &function.parameters {
+            self.add_parameter(parameter);
+        }
+
+        // Annotated parameters are checked, since caller may be unannotated.
+        for (index, parameter) in function.parameters.iter().enumerate() {
+            if let Some(annotation) = &parameter.annotation {
+                self.emitter.load(index as u32);
+                self.emitter
+                    .check_type(&types::type_name(self.module, &annotation.value))?;
+                self.emitter.drop()?;
+            }
+        }
+
+        self.block(&function.body)?;
+
+        if let Some(annotation) = &function.returns {
+            if !self.types.is_verified_return(function.span) {
+                self.emitter
+                    .check_type(&types::type_name(self.module, &annotation.value))?;
+            }
+        }
+
+        self.emitter.ret()?;
+
+        let mut scope = self.functions.pop().unwrap();
+        scope.parameters = args_count;
+
+        self.emitter
+            .function_end(index, scope.stack_size - args_count);
+
+        Ok(scope)
+    }
+
+    /// Reports local functions that were used, but never declared.
+    fn check_references(&mut self, unknown: Vec<(String, Span)>) {
+        for (name, span) in unknown {
+            let diagnostic = Diagnostic::error(
+                codes::UNKNOWN_FUNCTION,
+                format!("Unknown function \"{name}\"."),
+            )
+            .with_range(span.range());
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn function(&mut self, function: &ast::Function) -> let_result::Result {
+        self.span = function.name.span;
+        let full_name = qualify(self.module, &function.name.value);
+        self.emitter
+            .signature(&full_name, function.parameters.len() as u8);
+        self.emitter.label_named(full_name.clone())?;
+
+        let scope = self.function_body(function, full_name, false)?;
+        self.check_references(scope.unknown_references());
+        Ok(())
+    }
+
+    /*
+    Methods are functions labeled `module.Name.method`.
+    Label `module.Name` is a constructor which creates an instance
+    and calls its `init` method with constructor arguments.
+     */
+    fn class(&mut self, class: &ast::Class) -> let_result::Result {
+        let name = qualify(self.module, &class.name.value);
+        let parent = parent_name(self.module, class);
+
+        self.class = Some(name.clone());
+        let mut methods = Vec::new();
+        for method in &class.methods {
+            self.span = method.name.span;
+            let method_name = qualify(&name, &method.name.value);
+            self.emitter
+                .signature(&method_name, method.parameters.len() as u8);
+            self.emitter.label_named(method_name.clone())?;
+
+            let function = self.function_body(method, method_name, false)?;
+            self.check_references(function.unknown_references());
+            if function.parameters == 0 {
+                return self.fail(
+                    codes::MISSING_SELF,
+                    method.name.span,
+                    format!(
+                        "Method \"{}\" must have \"self\" parameter.",
+                        String::from_utf8_lossy(&method.name.value)
+                    ),
+                );
+            }
+            methods.push(method.name.value.clone());
+        }
+        self.class = None;
+
+        self.span = class.name.span;
+        let arguments = match self.constructors.get(&name) {
+            Some(Constructor::Arguments(arguments)) => *arguments,
+            Some(Constructor::Inherited(ancestor)) => match self.emitter.arguments(ancestor) {
+                Some(arguments) => arguments as u32,
+                None => {
+                    let diagnostic = Diagnostic::error(
+                        codes::UNKNOWN_CONSTRUCTOR,
+                        format!(
+                            "Class \"{}\" inherits constructor of unknown class \"{}\".",
+                            String::from_utf8_lossy(&class.name.value),
+                            String::from_utf8_lossy(ancestor)
+                        ),
+                    )
+                    .with_range(class.name.span.range())
+                    .with_help(
+                        "Compile module of the parent class first or pass its interface."
+                            .to_string(),
+                    );
+                    return Err(diagnostic.into());
+                }
+            },
+            None => 0,
+        };
+        self.constructor(&name, arguments)?;
+        self.emitter.class(let_module::ClassDescriptor {
+            name,
+            parent,
+            methods,
+        })
+    }
+
+    fn constructor(&mut self, name: &[u8], args_count: u32) -> let_result::Result {
+        self.emitter.signature(name, args_count as u8);
+        self.emitter
+            .label_named(Vec::from(name).into_boxed_slice())?;
+        let index = self
+            .emitter
+            .function(name, args_count as u8, self.span.range())?;
+        self.emitter.new_instance(name)?;
+        self.emitter.store(args_count);
+        self.emitter.drop()?;
+        self.emitter.void();
+        self.emitter.load(args_count);
+        for index in 0..args_count {
+            self.emitter.load(index);
+        }
+        self.emitter.invoke(b"init", args_count as u8 + 1)?;
+        self.emitter.drop()?;
+        self.emitter.load(args_count);
+        self.emitter.ret()?;
+        // One local for the created instance.
+        self.emitter.function_end(index, 1);
+        Ok(())
+    }
+
+    /// Function declared inside other function is visible only in its block.
+    /// Its value is a closure bound to the frame of enclosing function.
+    fn local_function(&mut self, function: &ast::Function) -> let_result::Result {
+        let end_id = self.get_lable_id() as u32;
+        self.emitter.jump(end_id)?;
+
+        let label = self.get_lable_id() as u32;
+        self.emitter.label(label)?;
+
+        let level = self.functions.len() - 1;
+        let block = self.functions.last_mut().unwrap().block();
+        let references = block
+            .references
+            .remove(&function.name.value)
+            .unwrap_or_default();
+        block.functions.insert(function.name.value.clone(), label);
+        for reference in references {
+            self.emitter.label(reference.label)?;
+            self.emitter
+                .set(reference.depth_address, (reference.level - level) as u8);
+        }
+
+        let name = qualify(&self.functions.last().unwrap().name, &function.name.value);
+        let scope = self.function_body(function, name, true)?;
+        self.functions
+            .last_mut()
+            .unwrap()
+            .block()
+            .forward(scope.blocks.into_iter().next().unwrap().references);
+
+        self.emitter.label(end_id)?;
+        self.closure(label, 0)?;
+        Ok(())
+    }
+
+    fn global_code(&mut self, expression: &Expression) -> let_result::Result {
+        self.span = expression.span;
+        self.emitter
+            .label_named(Vec::from(self.module).into_boxed_slice())?;
+        self.expression(expression)?;
+        let unknown = self.functions[0].unknown_references();
+        self.check_references(unknown);
+        self.emitter.ret()
+    }
+
+    fn item(&mut self, item: &ast::Item) -> let_result::Result {
+        match item {
+            ast::Item::Function(function) => self.function(function),
+            ast::Item::Class(class) => self.class(class),
+            ast::Item::Code(expression) => self.global_code(expression),
+        }
+    }
+
+    /// Generates code of module, diagnostics are available by
+    /// `diagnostics`. Errors are located at the node being generated.
+    pub fn generate(&mut self, module: &ast::Module) {
+        for item in &module.items {
+            if let ast::Item::Class(class) = item {
+                let arguments = match find_init(self.module, module, class) {
+                    Init::Method(init) => {
+                        Constructor::Arguments(init.parameters.len().saturating_sub(1) as u32)
+                    }
+                    Init::Missing => Constructor::Arguments(0),
+                    Init::Inherited(ancestor) => Constructor::Inherited(ancestor),
+                };
+                self.constructors
+                    .insert(qualify(self.module, &class.name.value), arguments);
+            }
+        }
+        for item in &module.items {
+            let depth = self.depth();
+            if let Err(error) = self.item(item) {
+                self.report(error);
+                self.recover(depth);
+                self.class = None;
+            }
+        }
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+}
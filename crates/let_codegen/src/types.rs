@@ -0,0 +1,789 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use let_ast::{self as ast, Expression, ExpressionKind, Name, Span};
+use let_result::{codes, Diagnostic};
+
+use crate::{find_init, parent_name, qualify, Init};
+
+/// Type names of annotations, other names are classes.
+const BUILTIN_TYPES: [&[u8]; 8] = [
+    b"bool",
+    b"function",
+    b"int",
+    b"list",
+    b"range",
+    b"real",
+    b"str",
+    b"void",
+];
+
+/// Name of annotated type checked by the VM, classes of the same module
+/// may be written unqualified.
+pub(crate) fn type_name(module: &[u8], annotation: &[u8]) -> Box<[u8]> {
+    if BUILTIN_TYPES.contains(&annotation) || annotation.contains(&b'.') {
+        annotation.into()
+    } else {
+        qualify(module, annotation)
+    }
+}
+
+/// Type of value known at compile time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Type {
+    /// Value of unannotated code, it is checked at run time.
+    Any,
+    Void,
+    Bool,
+    Int,
+    Real,
+    Str,
+    List,
+    Range,
+    Function,
+    /// Instance of class with full name.
+    Instance(Box<[u8]>),
+}
+
+impl Type {
+    fn from_name(name: Box<[u8]>) -> Type {
+        match name.as_ref() {
+            b"void" => Type::Void,
+            b"bool" => Type::Bool,
+            b"int" => Type::Int,
+            b"real" => Type::Real,
+            b"str" => Type::Str,
+            b"list" => Type::List,
+            b"range" => Type::Range,
+            b"function" => Type::Function,
+            _ => Type::Instance(name),
+        }
+    }
+
+    /// Type of value which is one of two types.
+    fn join(self, other: Type) -> Type {
+        if self == other {
+            self
+        } else {
+            Type::Any
+        }
+    }
+
+    fn is_primitive(&self) -> bool {
+        !matches!(self, Type::Any | Type::Instance(_))
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Type::Any => "any",
+            Type::Void => "void",
+            Type::Bool => "bool",
+            Type::Int => "int",
+            Type::Real => "real",
+            Type::Str => "str",
+            Type::List => "list",
+            Type::Range => "range",
+            Type::Function => "function",
+            Type::Instance(name) => return write!(f, "{}", String::from_utf8_lossy(name)),
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Whether value of known type matches annotation.
+enum Match {
+    Yes,
+    No,
+    /// Value must be checked at run time.
+    Unknown,
+}
+
+#[derive(Clone)]
+struct Variable {
+    ty: Type,
+    /// Annotated type and its location.
+    annotation: Option<(Type, Span)>,
+}
+
+type Scope = HashMap<Box<[u8]>, Variable>;
+
+/// Annotated parameter of signature.
+struct Parameter {
+    ty: Type,
+    name: Box<[u8]>,
+    /// Location of annotation.
+    span: Span,
+}
+
+struct Signature {
+    parameters: Vec<Option<Parameter>>,
+    returns: Type,
+}
+
+/// Values that are known to match their annotations at compile time,
+/// code generator emits runtime checks for the rest of them.
+#[derive(Default)]
+pub struct Types {
+    /// Spans of values assigned to annotated variables.
+    values: HashSet<Span>,
+    /// Spans of functions which returned value is verified.
+    returns: HashSet<Span>,
+}
+
+impl Types {
+    pub fn is_verified(&self, value: Span) -> bool {
+        self.values.contains(&value)
+    }
+
+    pub fn is_verified_return(&self, function: Span) -> bool {
+        self.returns.contains(&function)
+    }
+}
+
+/// Calls each direct child of expression, bodies of local functions
+/// are included.
+fn for_each_child<'e>(expression: &'e Expression, f: &mut impl FnMut(&'e Expression)) {
+    match &expression.kind {
+        ExpressionKind::Integer(_)
+        | ExpressionKind::Real(_)
+        | ExpressionKind::String(_)
+        | ExpressionKind::Identifier(_) => (),
+        ExpressionKind::Interpolation(items) | ExpressionKind::List(items) => {
+            items.iter().for_each(f)
+        }
+        ExpressionKind::Paren(expression) => f(expression),
+        ExpressionKind::Call { callee, arguments } => {
+            f(callee);
+            arguments.iter().for_each(f);
+        }
+        ExpressionKind::Super { arguments, .. } => arguments.iter().for_each(f),
+        ExpressionKind::Field { object, .. } => f(object),
+        ExpressionKind::Index { object, index, .. } => {
+            f(object);
+            f(index);
+        }
+        ExpressionKind::Assign { target, value } => {
+            f(target);
+            f(value);
+        }
+        ExpressionKind::Unary { operand, .. } => f(operand),
+        ExpressionKind::Binary { left, right, .. } => {
+            f(left);
+            f(right);
+        }
+        ExpressionKind::Comparison { first, rest } => {
+            f(first);
+            rest.iter().for_each(|(_, operand)| f(operand));
+        }
+        ExpressionKind::Range { start, end, .. } => {
+            start.iter().chain(end).for_each(|bound| f(bound));
+        }
+        ExpressionKind::If {
+            branches,
+            otherwise,
+        } => {
+            for branch in branches {
+                f(&branch.condition);
+                branch.body.iter().for_each(&mut *f);
+            }
+            otherwise.iter().flatten().for_each(f);
+        }
+        ExpressionKind::While { condition, body } => {
+            f(condition);
+            body.iter().for_each(f);
+        }
+        ExpressionKind::Let { value, .. } => f(value),
+        ExpressionKind::Function(function) => function.body.iter().for_each(f),
+    }
+}
+
+/// Collects names of variables assigned by `expression`, `nested` limits
+/// them to assignments made inside local functions.
+fn assigned<'e>(expression: &'e Expression, nested: bool, names: &mut HashSet<&'e [u8]>) {
+    match &expression.kind {
+        ExpressionKind::Assign { target, .. } if !nested => {
+            if let ExpressionKind::Identifier(name) = &target.kind {
+                names.insert(&name.value);
+            }
+        }
+        ExpressionKind::Function(function) if nested => {
+            for expression in &function.body {
+                assigned(expression, false, names);
+            }
+            return;
+        }
+        _ => (),
+    }
+    for_each_child(expression, &mut |child| assigned(child, nested, names));
+}
+
+/*
+   Checks annotated types of a module before code generation.
+
+   Types of variables are inferred from assigned values. Inference
+   follows control flow: after `if` a variable keeps its type only if all
+   branches agree on it, variables assigned in a loop and variables
+   assigned by local functions are unknown. Values of parameters, fields,
+   indexing and calls of unannotated functions are unknown too.
+
+   Value of known type that doesn't match annotation is an error. Value of
+   unknown type is checked at run time, as well as arguments of annotated
+   parameters, since functions may be called from other modules.
+*/
+pub struct TypeChecker<'a> {
+    module: &'a [u8],
+    /// Classes of the module with their parents.
+    classes: HashMap<Box<[u8]>, Option<Box<[u8]>>>,
+    /// Functions and constructors of the module by full name.
+    signatures: HashMap<Box<[u8]>, Signature>,
+    scopes: Vec<Scope>,
+    /// Variables of global code are kept between its items.
+    global: Vec<Scope>,
+    /// Index of the first scope of current function, scopes below it
+    /// belong to enclosing functions.
+    function_scope: usize,
+    /// Variables assigned by local functions, their type may change
+    /// at any call.
+    unstable: HashSet<Box<[u8]>>,
+    errors: Vec<Diagnostic>,
+    types: Types,
+}
+
+impl<'a> TypeChecker<'a> {
+    pub fn new(module: &'a [u8]) -> Self {
+        Self {
+            module,
+            classes: HashMap::new(),
+            signatures: HashMap::new(),
+            scopes: Vec::new(),
+            global: vec![Scope::new()],
+            function_scope: 0,
+            unstable: HashSet::new(),
+            errors: Vec::new(),
+            types: Types::default(),
+        }
+    }
+
+    fn mismatch(&mut self, expected: &Type, found: &Type, span: Span, label: (Span, String)) {
+        self.errors.push(
+            Diagnostic::error(
+                codes::TYPE_MISMATCH,
+                format!("Expected {expected}, found {found}."),
+            )
+            .with_range(span.range())
+            .with_label(label.0.range(), label.1),
+        );
+    }
+
+    /// Resolves annotation, unknown type is reported and treated as any.
+    fn annotation(&mut self, name: &Name) -> Type {
+        let full_name = type_name(self.module, &name.value);
+        let ty = Type::from_name(full_name.clone());
+        // Classes of other modules are not known.
+        let local =
+            full_name.starts_with(self.module) && full_name.get(self.module.len()) == Some(&b'.');
+        if matches!(ty, Type::Instance(_)) && local && !self.classes.contains_key(&full_name) {
+            self.errors.push(
+                Diagnostic::error(
+                    codes::UNKNOWN_TYPE,
+                    format!("Unknown type \"{}\".", String::from_utf8_lossy(&name.value)),
+                )
+                .with_range(name.span.range()),
+            );
+            return Type::Any;
+        }
+        ty
+    }
+
+    /// Checks if instance of `class` is instance of `expected` class.
+    fn is_class(&self, class: &[u8], expected: &[u8]) -> Match {
+        let mut current = class;
+        // Inheritance cycles are reported by the VM.
+        for _ in 0..=self.classes.len() {
+            if current == expected {
+                return Match::Yes;
+            }
+            match self.classes.get(current) {
+                Some(Some(parent)) => current = parent,
+                Some(None) => return Match::No,
+                None => break,
+            }
+        }
+        Match::Unknown
+    }
+
+    fn matches(&self, expected: &Type, found: &Type) -> Match {
+        match (expected, found) {
+            (Type::Any, _) | (_, Type::Any) => Match::Unknown,
+            (Type::Instance(expected), Type::Instance(found)) => self.is_class(found, expected),
+            _ if expected == found => Match::Yes,
+            _ => Match::No,
+        }
+    }
+
+    /// Checks value assigned to annotated slot, verified values are not
+    /// checked at run time.
+    fn check_value(
+        &mut self,
+        expected: &Type,
+        found: &Type,
+        span: Span,
+        label: (Span, String),
+    ) -> bool {
+        match self.matches(expected, found) {
+            Match::Yes => true,
+            Match::No => {
+                self.mismatch(expected, found, span, label);
+                false
+            }
+            Match::Unknown => false,
+        }
+    }
+
+    fn lookup(&self, name: &[u8]) -> Option<(usize, &Variable)> {
+        self.scopes
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, scope)| Some((index, scope.get(name)?)))
+    }
+
+    /// Type of variable visible in current function.
+    fn variable(&self, name: &[u8]) -> Option<Type> {
+        let (index, variable) = self.lookup(name)?;
+        if let Some((ty, _)) = &variable.annotation {
+            return Some(ty.clone());
+        }
+        if index < self.function_scope || self.unstable.contains(name) {
+            return Some(Type::Any);
+        }
+        Some(variable.ty.clone())
+    }
+
+    /// Sets unannotated variables to unknown type.
+    fn forget(&mut self, names: &HashSet<&[u8]>) {
+        for scope in &mut self.scopes[self.function_scope..] {
+            for (name, variable) in scope.iter_mut() {
+                if names.contains(name.as_ref()) {
+                    variable.ty = Type::Any;
+                }
+            }
+        }
+    }
+
+    fn declare(&mut self, name: &Name, ty: Type, annotation: Option<(Type, Span)>) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.value.clone(), Variable { ty, annotation });
+    }
+
+    /// Joins variable types of several control flow paths.
+    fn join(&mut self, states: Vec<Vec<Scope>>) {
+        let mut states = states.into_iter();
+        let mut scopes = states.next().unwrap();
+        for state in states {
+            for (scope, other) in scopes.iter_mut().zip(state) {
+                for (name, variable) in scope.iter_mut() {
+                    let ty = other.get(name).map_or(Type::Any, |other| other.ty.clone());
+                    variable.ty = std::mem::replace(&mut variable.ty, Type::Any).join(ty);
+                }
+            }
+        }
+        self.scopes = scopes;
+    }
+
+    fn block(&mut self, expressions: &[Expression]) -> Type {
+        self.scopes.push(Scope::new());
+        let mut ty = Type::Void;
+        for expression in expressions {
+            ty = self.expression(expression);
+        }
+        self.scopes.pop();
+        ty
+    }
+
+    fn call(&mut self, callee: &Expression, arguments: &[Expression]) -> Type {
+        let types: Vec<Type> = arguments
+            .iter()
+            .map(|argument| self.expression(argument))
+            .collect();
+        let name = match &callee.kind {
+            ExpressionKind::Identifier(name) => name,
+            _ => {
+                self.expression(callee);
+                return Type::Any;
+            }
+        };
+        // Method call like `object.method()` is dispatched at run time.
+        let object = name.value.split(|&c| c == b'.').next().unwrap();
+        if self.lookup(object).is_some() {
+            return Type::Any;
+        }
+        let signature = match self.signatures.get(&name.value) {
+            Some(signature) if signature.parameters.len() == arguments.len() => signature,
+            _ => return Type::Any,
+        };
+        let mut mismatches = Vec::new();
+        for ((parameter, ty), argument) in signature.parameters.iter().zip(types).zip(arguments) {
+            if let Some(Parameter {
+                ty: expected,
+                name,
+                span,
+            }) = parameter
+            {
+                if let Match::No = self.matches(expected, &ty) {
+                    let label = format!(
+                        "Parameter \"{}\" is declared as {expected} here.",
+                        String::from_utf8_lossy(name)
+                    );
+                    mismatches.push((expected.clone(), ty, argument.span, (*span, label)));
+                }
+            }
+        }
+        let returns = signature.returns.clone();
+        for (expected, found, span, label) in mismatches {
+            self.mismatch(&expected, &found, span, label);
+        }
+        returns
+    }
+
+    fn assign(&mut self, target: &Expression, value: &Expression) -> Type {
+        let ty = self.expression(value);
+        let name = match &target.kind {
+            ExpressionKind::Identifier(name) if !name.value.contains(&b'.') => name,
+            _ => {
+                self.expression(target);
+                return ty;
+            }
+        };
+        let (index, variable) = match self.lookup(&name.value) {
+            Some((index, variable)) => (index, variable.clone()),
+            None => return ty,
+        };
+        match variable.annotation {
+            Some((expected, span)) => {
+                let label = format!(
+                    "\"{}\" is declared as {expected} here.",
+                    String::from_utf8_lossy(&name.value)
+                );
+                if self.check_value(&expected, &ty, value.span, (span, label)) {
+                    self.types.values.insert(value.span);
+                }
+            }
+            None => self.scopes[index].get_mut(&name.value).unwrap().ty = ty.clone(),
+        }
+        ty
+    }
+
+    fn p_let(&mut self, name: &Name, annotation: Option<&Name>, value: &Expression) -> Type {
+        let ty = self.expression(value);
+        match annotation {
+            Some(annotation) => {
+                let expected = self.annotation(annotation);
+                let label = format!(
+                    "\"{}\" is declared as {expected} here.",
+                    String::from_utf8_lossy(&name.value)
+                );
+                if self.check_value(&expected, &ty, value.span, (annotation.span, label)) {
+                    self.types.values.insert(value.span);
+                }
+                self.declare(name, expected.clone(), Some((expected, annotation.span)));
+            }
+            None => self.declare(name, ty.clone(), None),
+        }
+        ty
+    }
+
+    fn p_if(&mut self, branches: &[ast::Branch], otherwise: Option<&[Expression]>) -> Type {
+        let mut states = Vec::new();
+        let mut result: Option<Type> = None;
+        for branch in branches {
+            self.expression(&branch.condition);
+            let state = self.scopes.clone();
+            let ty = self.block(&branch.body);
+            result = Some(result.map_or(ty.clone(), |result| result.join(ty)));
+            states.push(std::mem::replace(&mut self.scopes, state));
+        }
+        let ty = match otherwise {
+            Some(body) => self.block(body),
+            None => Type::Void,
+        };
+        states.push(std::mem::take(&mut self.scopes));
+        self.join(states);
+        result.unwrap().join(ty)
+    }
+
+    fn p_while(&mut self, condition: &Expression, body: &[Expression]) -> Type {
+        let mut names = HashSet::new();
+        assigned(condition, false, &mut names);
+        for expression in body {
+            assigned(expression, false, &mut names);
+        }
+        self.forget(&names);
+        self.expression(condition);
+        self.block(body);
+        self.forget(&names);
+        Type::Any
+    }
+
+    fn unary(&mut self, operator: &ast::Operator, operand: &Expression) -> Type {
+        match (operator, self.expression(operand)) {
+            (b"-  ", ty @ (Type::Int | Type::Real)) => ty,
+            (b"!  ", Type::Bool) => Type::Bool,
+            _ => Type::Any,
+        }
+    }
+
+    /// Operators of instances are hooks, so only operators of primitive
+    /// types are inferred.
+    fn binary(&mut self, operator: &ast::Operator, left: &Expression, right: &Expression) -> Type {
+        let left = self.expression(left);
+        let right = self.expression(right);
+        match (operator, left, right) {
+            (b".. " | b"..=", _, _) => Type::Range,
+            (b"+  ", Type::Str, Type::Str) => Type::Str,
+            (b"+  " | b"-  " | b"*  " | b"/  " | b"%  ", Type::Int, Type::Int) => Type::Int,
+            (b"+  " | b"-  " | b"*  " | b"/  ", Type::Real, Type::Real) => Type::Real,
+            (b"/  " | b"%  ", Type::Int | Type::Real, Type::Int | Type::Real) => Type::Real,
+            (b"<< " | b">> ", Type::Int, Type::Int) => Type::Int,
+            (b"== " | b"!= " | b"<  " | b">  " | b"<= " | b">= ", left, right)
+                if left.is_primitive() && right.is_primitive() =>
+            {
+                Type::Bool
+            }
+            _ => Type::Any,
+        }
+    }
+
+    fn comparison(&mut self, first: &Expression, rest: &[(ast::Operator, Expression)]) -> Type {
+        let mut primitive = self.expression(first).is_primitive();
+        for (_, operand) in rest {
+            primitive &= self.expression(operand).is_primitive();
+        }
+        if primitive {
+            Type::Bool
+        } else {
+            Type::Any
+        }
+    }
+
+    fn identifier(&mut self, name: &Name) -> Type {
+        if let Some(ty) = self.variable(&name.value) {
+            return ty;
+        }
+        match name.value.iter().position(|&c| c == b'.') {
+            // Field of variable.
+            Some(dot) if self.lookup(&name.value[..dot]).is_some() => Type::Any,
+            // Pointer to function of module.
+            Some(_) => Type::Function,
+            None => Type::Any,
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Type {
+        match &expression.kind {
+            ExpressionKind::Integer(_) => Type::Int,
+            ExpressionKind::Real(_) => Type::Real,
+            ExpressionKind::String(_) => Type::Str,
+            ExpressionKind::Interpolation(parts) => {
+                for part in parts {
+                    self.expression(part);
+                }
+                Type::Str
+            }
+            ExpressionKind::List(items) => {
+                for item in items {
+                    self.expression(item);
+                }
+                Type::List
+            }
+            ExpressionKind::Identifier(name) => self.identifier(name),
+            ExpressionKind::Paren(expression) => self.expression(expression),
+            ExpressionKind::Call { callee, arguments } => self.call(callee, arguments),
+            ExpressionKind::Assign { target, value } => self.assign(target, value),
+            ExpressionKind::Unary { operator, operand } => self.unary(operator, operand),
+            ExpressionKind::Binary {
+                operator,
+                left,
+                right,
+            } => match operator {
+                b"?? " => {
+                    self.expression(left);
+                    self.expression(right);
+                    Type::Any
+                }
+                _ => self.binary(operator, left, right),
+            },
+            ExpressionKind::Comparison { first, rest } => self.comparison(first, rest),
+            ExpressionKind::If {
+                branches,
+                otherwise,
+            } => self.p_if(branches, otherwise.as_deref()),
+            ExpressionKind::While { condition, body } => self.p_while(condition, body),
+            ExpressionKind::Let {
+                name,
+                annotation,
+                value,
+                ..
+            } => self.p_let(name, annotation.as_ref(), value),
+            ExpressionKind::Function(function) => {
+                self.function(function);
+                Type::Function
+            }
+            ExpressionKind::Range { .. }
+            | ExpressionKind::Super { .. }
+            | ExpressionKind::Field { .. }
+            | ExpressionKind::Index { .. } => {
+                for_each_child(expression, &mut |child| {
+                    self.expression(child);
+                });
+                match expression.kind {
+                    ExpressionKind::Range { .. } => Type::Range,
+                    _ => Type::Any,
+                }
+            }
+        }
+    }
+
+    /// Checks body of function in new scope above the current ones.
+    fn function(&mut self, function: &ast::Function) {
+        let function_scope = std::mem::replace(&mut self.function_scope, self.scopes.len());
+        self.scopes.push(Scope::new());
+        for parameter in &function.parameters {
+            let annotation = parameter
+                .annotation
+                .as_ref()
+                .map(|annotation| (self.annotation(annotation), annotation.span));
+            let ty = annotation.as_ref().map_or(Type::Any, |(ty, _)| ty.clone());
+            self.declare(&parameter.name, ty, annotation);
+        }
+        let ty = self.block(&function.body);
+        if let Some(annotation) = &function.returns {
+            let expected = self.annotation(annotation);
+            let span = function
+                .body
+                .last()
+                .map_or(function.name.span, |expression| expression.span);
+            let label = (annotation.span, "Return type is declared here.".to_string());
+            if self.check_value(&expected, &ty, span, label) {
+                self.types.returns.insert(function.span);
+            }
+        }
+        self.scopes.pop();
+        self.function_scope = function_scope;
+    }
+
+    /// Functions of module don't see variables of global code.
+    fn item_function(&mut self, function: &ast::Function) {
+        let global = std::mem::take(&mut self.scopes);
+        self.function(function);
+        self.scopes = global;
+    }
+
+    /// Collects classes and signatures of functions which can be called
+    /// directly, constructors take parameters of `init` without `self`.
+    fn declarations(&mut self, module: &ast::Module) {
+        for item in &module.items {
+            if let ast::Item::Class(class) = item {
+                self.classes.insert(
+                    qualify(self.module, &class.name.value),
+                    parent_name(self.module, class),
+                );
+            }
+        }
+        for item in &module.items {
+            let (name, parameters, returns) = match item {
+                ast::Item::Function(function) => (
+                    qualify(self.module, &function.name.value),
+                    &function.parameters[..],
+                    function
+                        .returns
+                        .as_ref()
+                        .map(|returns| self.annotation(returns)),
+                ),
+                ast::Item::Class(class) => {
+                    let name = qualify(self.module, &class.name.value);
+                    let parameters = match find_init(self.module, module, class) {
+                        Init::Method(init) => init.parameters.get(1..).unwrap_or_default(),
+                        Init::Missing => &[],
+                        // Parameters of other modules are not known.
+                        Init::Inherited(_) => continue,
+                    };
+                    let returns = Type::Instance(name.clone());
+                    (name, parameters, Some(returns))
+                }
+                ast::Item::Code(_) => continue,
+            };
+            let parameters = parameters
+                .iter()
+                .map(|parameter| {
+                    let annotation = parameter.annotation.as_ref()?;
+                    Some(Parameter {
+                        ty: self.annotation(annotation),
+                        name: parameter.name.value.clone(),
+                        span: annotation.span,
+                    })
+                })
+                .collect();
+            let signature = Signature {
+                parameters,
+                returns: returns.unwrap_or(Type::Any),
+            };
+            self.signatures.insert(name, signature);
+        }
+    }
+
+    /// Checks module, diagnostics are available by `errors`.
+    pub fn check(&mut self, module: &ast::Module) -> Types {
+        self.declarations(module);
+        // Unknown types of signatures are reported again by functions.
+        self.errors.clear();
+        let mut global = HashSet::new();
+        for item in &module.items {
+            if let ast::Item::Code(expression) = item {
+                assigned(expression, true, &mut global);
+            }
+        }
+        let global: HashSet<Box<[u8]>> = global.into_iter().map(Box::from).collect();
+        for item in &module.items {
+            let mut unstable = HashSet::new();
+            match item {
+                ast::Item::Function(function) => {
+                    function
+                        .body
+                        .iter()
+                        .for_each(|expression| assigned(expression, true, &mut unstable));
+                    self.unstable = unstable.into_iter().map(Box::from).collect();
+                    self.item_function(function);
+                }
+                ast::Item::Class(class) => {
+                    for method in &class.methods {
+                        method
+                            .body
+                            .iter()
+                            .for_each(|expression| assigned(expression, true, &mut unstable));
+                    }
+                    self.unstable = unstable.into_iter().map(Box::from).collect();
+                    for method in &class.methods {
+                        self.item_function(method);
+                    }
+                }
+                ast::Item::Code(expression) => {
+                    self.unstable = global.clone();
+                    self.scopes = std::mem::take(&mut self.global);
+                    self.function_scope = 0;
+                    self.expression(expression);
+                    self.global = std::mem::take(&mut self.scopes);
+                }
+            }
+        }
+        std::mem::take(&mut self.types)
+    }
+
+    pub fn errors(&self) -> &[Diagnostic] {
+        &self.errors
+    }
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::{run, run_with};
+
+fn eval(source: &str) -> String {
+    run(&[("main", source)])
+}
+
+fn eval_checked(source: &str) -> String {
+    run_with(&[("main", source)], true)
+}
+
+#[test]
+fn shifts() {
+    assert_eq!(
+        eval("[1 << 62 1 << 63 (-1) << 64 (-5) >> 100 5 >> 64 (-8) >> 1]"),
+        "[4611686018427387904, 9223372036854775808, -18446744073709551616, -1, 0, -4]"
+    );
+    assert_eq!(
+        eval("[(1 << 100) >> 98 (-(1 << 100) - 1) >> 99 (1 << 100) << 1 >> 101]"),
+        "[4, -3, 1]"
+    );
+}
+
+#[test]
+fn negative_shift_amount() {
+    assert_eq!(eval("1 << -1"), "Negative shift amount in 1 << -1.");
+    assert_eq!(eval_checked("1 >> -1"), "Negative shift amount in 1 >> -1.");
+}
+
+#[test]
+fn checked_shifts() {
+    assert_eq!(
+        eval_checked("[1 << 62 (-8) >> 1]"),
+        "[4611686018427387904, -4]"
+    );
+    assert_eq!(eval_checked("1 << 63"), "Integer overflow in 1 << 63.");
+    assert_eq!(
+        eval_checked("1 >> 64"),
+        "Shift amount is out of range in 1 >> 64."
+    );
+}
+
+#[test]
+fn mixed_division() {
+    assert_eq!(
+        eval("[5 / 2 5 / 2.0 5.0 / 2 7 % 2.5 (-7.5) % 2]"),
+        "[2, 2.5, 2.5, 2, -1.5]"
+    );
+    assert_eq!(eval("(1 << 70) / 2.0"), "590295810358705700000");
+}
+
+#[test]
+fn deep_recursion() {
+    let utils = "
+fn factorial(n)
+    if n <= 1
+        1
+    else
+        n * utils.factorial(n - 1)
+    end
+end";
+    assert_eq!(
+        run(&[("utils", utils), ("main", "utils.factorial(25)")]),
+        "15511210043330985984000000"
+    );
+    let sum = "
+fn sum(n)
+    if n == 0
+        0
+    else
+        n + main.sum(n - 1)
+    end
+end
+main.sum(100)";
+    assert_eq!(eval(sum), "5050");
+}
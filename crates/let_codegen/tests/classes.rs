@@ -0,0 +1,62 @@
+mod common;
+
+use common::{compile, run};
+
+const ACCOUNT: &str = "
+class Account
+    fn init(self balance)
+        self.balance = balance
+    end
+end
+";
+
+#[test]
+fn inherited_constructor() {
+    let source = format!(
+        "{ACCOUNT}
+class Savings(Account)
+    fn rate(self)
+        3
+    end
+end
+[main.Savings(10).balance main.Savings(10).rate()]"
+    );
+    assert_eq!(run(&[("main", &source)]), "[10, 3]");
+}
+
+#[test]
+fn constructor_inherited_from_grandparent() {
+    let source = "
+class Savings(Account)
+end
+class Deposit(Savings)
+end
+class Account
+    fn init(self balance)
+        self.balance = balance
+    end
+end
+main.Deposit(7).balance";
+    assert_eq!(run(&[("main", source)]), "7");
+}
+
+#[test]
+fn constructor_inherited_from_other_module() {
+    let source = "
+class Savings(bank.Account)
+end
+main.Savings(5).balance";
+    assert_eq!(run(&[("bank", ACCOUNT), ("main", source)]), "5");
+}
+
+#[test]
+fn constructor_of_unknown_class() {
+    let source = "
+class Savings(bank.Account)
+end
+main.Savings(5)";
+    let mut emitter = let_emitter::Emitter::new();
+    let diagnostics = compile(&mut emitter, "main", source);
+    let codes: Vec<&str> = diagnostics.iter().map(|d| d.code).collect();
+    assert_eq!(codes, [let_result::codes::UNKNOWN_CONSTRUCTOR]);
+}
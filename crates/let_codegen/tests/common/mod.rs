@@ -0,0 +1,63 @@
+// Each test uses a part of the helpers.
+#![allow(dead_code)]
+
+use let_result::Diagnostic;
+
+/// Compiles module `name` from `source` into `emitter`, returns
+/// diagnostics of type checker, generator and linker.
+pub fn compile(emitter: &mut let_emitter::Emitter, name: &str, source: &str) -> Vec<Diagnostic> {
+    let mut parser = let_parser::Parser::new(source.bytes());
+    let ast = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    let mut checker = let_codegen::TypeChecker::new(name.as_bytes());
+    let types = checker.check(&ast);
+    let mut diagnostics = checker.errors().to_vec();
+    let mut generator = let_codegen::Generator::new(name.as_bytes(), emitter).with_types(types);
+    generator.generate(&ast);
+    diagnostics.extend_from_slice(generator.diagnostics());
+    if diagnostics.is_empty() {
+        match emitter.resolve() {
+            Ok(()) => diagnostics.extend(emitter.undefined()),
+            Err(error) => diagnostics.push(error.into_diagnostic()),
+        }
+    }
+    diagnostics
+}
+
+/// Compiles modules in order and runs module `main`.
+pub fn run(modules: &[(&str, &str)]) -> String {
+    run_with(modules, false)
+}
+
+/// Runs modules in checked arithmetic mode if `checked`.
+pub fn run_with(modules: &[(&str, &str)], checked: bool) -> String {
+    let mut emitter = let_emitter::Emitter::new();
+    for (name, source) in modules {
+        let diagnostics = compile(&mut emitter, name, source);
+        assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    }
+    execute(&emitter.into_module(), checked)
+}
+
+/// Compiles module `name` alone and reads it back, like `letc` and
+/// `letl` do.
+pub fn module(name: &str, source: &str) -> let_module::Module {
+    let mut emitter = let_emitter::Emitter::new();
+    emitter.set_source(&format!("{name}.let")).unwrap();
+    let diagnostics = compile(&mut emitter, name, source);
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    let mut bytes = Vec::new();
+    emitter.write(&mut bytes).unwrap();
+    let_module::Module::read(bytes.as_slice()).unwrap()
+}
+
+/// Runs linked `module` from module `main`.
+pub fn execute(module: &let_module::Module, checked: bool) -> String {
+    let mut state = let_vm::State::new();
+    state.set_checked(checked);
+    state.set_pc(module.labels.get(b"main").unwrap());
+    match state.run(module) {
+        Ok(result) => result.to_string(),
+        Err(_) => state.message().unwrap().to_string(),
+    }
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::compile;
+use let_result::codes;
+
+#[test]
+fn errors_of_every_statement_are_reported() {
+    let source = "
+fn first()
+    let a = 1
+    a = unknown + 1
+    a.x = other
+end
+fn second()
+    const c = 1
+    c = 2
+    super.f()
+end
+class A
+    fn m()
+        missing
+    end
+end
+main.second()";
+    let mut emitter = let_emitter::Emitter::new();
+    let diagnostics = compile(&mut emitter, "main", source);
+    let errors: Vec<(&str, &str)> = diagnostics
+        .iter()
+        .map(|d| (d.code, &source[d.range.clone().unwrap()]))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (codes::UNKNOWN_FUNCTION, "unknown"),
+            (codes::UNKNOWN_FUNCTION, "other"),
+            (codes::CONST_ASSIGN, "c"),
+            (codes::SUPER_OUTSIDE_CLASS, "super.f()"),
+            (codes::UNKNOWN_FUNCTION, "missing"),
+            (codes::MISSING_SELF, "m"),
+        ]
+    );
+}
+
+#[test]
+fn every_undefined_symbol_is_reported() {
+    let mut emitter = let_emitter::Emitter::new();
+    assert!(compile(&mut emitter, "lib", "fn f()\n    1\nend").is_empty());
+    let source = "[lib.zed() main.g() lib.alpha() lib.zed()]";
+    emitter.set_source("main.let").unwrap();
+    let diagnostics = compile(&mut emitter, "main", source);
+    let errors: Vec<(&str, &str, Vec<&str>)> = diagnostics
+        .iter()
+        .map(|d| {
+            let labels = d.labels.iter().map(|l| &source[l.range.clone()]).collect();
+            (d.code, &source[d.range.clone().unwrap()], labels)
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (codes::UNDEFINED_SYMBOL, "lib.alpha", vec![]),
+            (codes::UNDEFINED_SYMBOL, "lib.zed", vec!["lib.zed"]),
+            (codes::UNDEFINED_SYMBOL, "main.g", vec![]),
+        ]
+    );
+    // The first reference is primary location.
+    assert_eq!(diagnostics[1].range, Some(1..8));
+}
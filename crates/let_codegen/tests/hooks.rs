@@ -0,0 +1,56 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn comparison_falls_back_to_right_operand() {
+    let source = "
+class Weight
+    fn init(self value)
+        self.value = value
+    end
+    fn lt(self other)
+        self.value < other.value
+    end
+end
+class Plain
+    fn init(self value)
+        self.value = value
+    end
+end
+fn main()
+    let w = main.Weight(2)
+    let p = main.Plain(3)
+    [w < p p < w p > w p <= w p >= w w > p w <= p w >= p]
+end
+main.main()";
+    assert_eq!(
+        run(&[("main", source)]),
+        "[true, false, true, false, true, false, true, false]"
+    );
+}
+
+#[test]
+fn index_assignment() {
+    let source = "
+class Grid
+    fn init(self)
+        self.items = [0 0]
+    end
+    fn index(self key)
+        let items = self.items
+        items[key]
+    end
+    fn set_index(self key value)
+        let items = self.items
+        items[key] = value * 10
+    end
+end
+fn main()
+    let g = main.Grid()
+    let a = g[1] = 4
+    [a g[1] g[0]]
+end
+main.main()";
+    assert_eq!(run(&[("main", source)]), "[4, 40, 0]");
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::{compile, execute, module};
+use let_result::codes;
+
+/// Library with a function of `PTR` arguments and `JP` locals, which
+/// bytes were once misread as instructions while merging.
+fn wide_library() -> String {
+    let count = let_opcodes::PTR as usize;
+    let parameters: Vec<String> = (0..count).map(|i| format!("p{i}")).collect();
+    let mut source = format!("fn wide({})\n", parameters.join(" "));
+    for i in 0..let_opcodes::JP as usize {
+        source.push_str(&format!("    let l{i} = p{i}\n"));
+    }
+    source.push_str(&format!(
+        "    l0 + l{} + p{}\nend\n",
+        let_opcodes::JP - 1,
+        count - 1
+    ));
+    source.push_str("fn pick(x)\n    if x > 0\n        x\n    else\n        0\n    end\nend\n");
+    source
+}
+
+fn wide_call() -> String {
+    let arguments: Vec<String> = (0..let_opcodes::PTR).map(|i| i.to_string()).collect();
+    format!(
+        "[lib.wide({}) lib.pick(5) lib.pick(0 - 1)]",
+        arguments.join(" ")
+    )
+}
+
+#[test]
+fn merge_doesnt_decode_function_headers() {
+    let library = wide_library();
+    let lib = module("lib", &library);
+    let wide = lib
+        .functions
+        .iter()
+        .find(|f| &*f.name == b"lib.wide")
+        .unwrap();
+    assert_eq!(wide.arguments, let_opcodes::PTR);
+    assert_eq!(wide.stack_size, let_opcodes::JP as u32);
+
+    let main = wide_call();
+    let mut program = module("main", &main);
+    program.merge(lib).unwrap();
+    assert!(program.check_undefined().is_empty());
+    assert_eq!(execute(&program, false), "[225, 5, 0]");
+
+    // Code of main is moved by merge in the other order.
+    let mut program = module("lib", &library);
+    program.merge(module("main", &main)).unwrap();
+    assert_eq!(execute(&program, false), "[225, 5, 0]");
+}
+
+const PAIR: &str = "
+fn f(a b)
+    a + b
+end
+";
+
+const CALL: &str = "lib.f(1)";
+
+#[test]
+fn arity_is_checked_by_merge() {
+    let mut program = module("main", CALL);
+    let error = program.merge(module("lib", PAIR)).err().unwrap();
+    let diagnostic = error.into_diagnostic();
+    assert_eq!(diagnostic.code, codes::ARITY_MISMATCH);
+    assert_eq!(&CALL[diagnostic.range.unwrap()], "lib.f");
+    assert_eq!(diagnostic.file.as_deref(), Some("main.let"));
+}
+
+#[test]
+fn arity_is_checked_against_interface() {
+    let mut lib = let_emitter::Emitter::new();
+    assert!(compile(&mut lib, "lib", PAIR).is_empty());
+    let mut bytes = Vec::new();
+    lib.interface().write(&mut bytes).unwrap();
+    let interface = let_module::Interface::read(bytes.as_slice()).unwrap();
+    assert_eq!(interface.signatures.get(b"lib.f"), Some(2));
+
+    let mut emitter = let_emitter::Emitter::new();
+    emitter.set_source("main.let").unwrap();
+    emitter.import(interface);
+    let diagnostics = compile(&mut emitter, "main", CALL);
+    let found: Vec<&str> = diagnostics.iter().map(|d| d.code).collect();
+    assert_eq!(found, [codes::ARITY_MISMATCH]);
+    assert_eq!(&CALL[diagnostics[0].range.clone().unwrap()], "lib.f");
+}
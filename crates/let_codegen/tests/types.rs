@@ -0,0 +1,80 @@
+mod common;
+
+use common::{compile, run};
+use let_result::codes;
+
+/// Compiles module `main`, returns codes of diagnostics with their text.
+fn check(source: &str) -> Vec<(&'static str, &str)> {
+    let mut emitter = let_emitter::Emitter::new();
+    compile(&mut emitter, "main", source)
+        .into_iter()
+        .map(|d| (d.code, &source[d.range.unwrap()]))
+        .collect()
+}
+
+#[test]
+fn let_annotation_mismatch() {
+    let source = "
+fn main()
+    let x: int = \"a\"
+    x
+end
+main.main()";
+    assert_eq!(check(source), [(codes::TYPE_MISMATCH, "\"a\"")]);
+}
+
+#[test]
+fn return_type_mismatch() {
+    let source = "
+fn name() -> int
+    \"a\"
+end
+main.name()";
+    assert_eq!(check(source), [(codes::TYPE_MISMATCH, "\"a\"")]);
+}
+
+#[test]
+fn argument_type_mismatch() {
+    let source = "
+fn twice(n: int) -> int
+    n * 2
+end
+main.twice(\"a\")";
+    assert_eq!(check(source), [(codes::TYPE_MISMATCH, "\"a\"")]);
+}
+
+#[test]
+fn unknown_type() {
+    let source = "
+fn main()
+    let x: Point = 1
+    x
+end
+main.main()";
+    assert_eq!(check(source), [(codes::UNKNOWN_TYPE, "Point")]);
+}
+
+const TWICE: &str = "
+fn twice(n: int) -> int
+    n * 2
+end
+fn id(x)
+    x
+end
+";
+
+#[test]
+fn unverified_argument_is_checked_at_run_time() {
+    let source = format!("{TWICE}main.twice(main.id(\"a\"))");
+    assert!(check(&source).is_empty());
+    assert_eq!(run(&[("main", &source)]), "Expected int, found str.");
+}
+
+#[test]
+fn big_integer_is_int() {
+    let source = format!("{TWICE}[main.twice(1 << 70) main.twice(main.id(1 << 70))]");
+    assert_eq!(
+        run(&[("main", &source)]),
+        "[2361183241434822606848, 2361183241434822606848]"
+    );
+}
@@ -1,38 +1,61 @@
-use std::fmt;
-
-struct Slice<'a>(&'a [u8]);
-
-impl<'a> fmt::Display for Slice<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for c in self.0.iter().cloned() {
-            if c != b' ' {
-                write!(f, "{}", c as char)?;
-            }
-        }
-        Ok(())
-    }
-}
-
 pub struct Emitter {
     opcodes: Vec<u8>,
+    relocations: let_module::Relocations,
+    /// Opcode extensions used by emitted code.
+    features: u32,
     named_labels: let_module::NamedLabels,
     named_links: let_module::NamedLinks,
     indexed_labels: let_module::IndexedLabels,
     indexed_links: let_module::IndexedLinks,
+    strings: let_module::Strings,
+    classes: let_module::Classes,
+    signatures: let_module::Signatures,
+    /// Signatures of other modules from their interfaces.
+    imported: let_module::Signatures,
+    calls: let_module::Calls,
+    functions: let_module::Functions,
+    /// String index of path of source file being compiled.
+    source: Option<u32>,
+}
+
+impl Default for Emitter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Emitter {
     pub fn new() -> Self {
         Self {
             opcodes: Vec::new(),
+            relocations: let_module::Relocations::new(),
+            features: 0,
             named_labels: let_module::NamedLabels::new(),
             named_links: let_module::NamedLinks::new(),
             indexed_labels: let_module::IndexedLabels::new(),
             indexed_links: let_module::IndexedLinks::new(),
+            strings: let_module::Strings::new(),
+            classes: let_module::Classes::new(),
+            signatures: let_module::Signatures::new(),
+            imported: let_module::Signatures::new(),
+            calls: let_module::Calls::new(),
+            functions: let_module::Functions::new(),
+            source: None,
         }
     }
 
-    pub fn integer(&mut self, value: u64) -> let_result::Result {
+    /// Emits non-negative integer given by its big-endian bytes.
+    /// Integers beyond `i64` are stored in string constants.
+    pub fn integer(&mut self, bytes: &[u8]) -> let_result::Result {
+        let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+        let bytes = &bytes[start..];
+        if bytes.len() > 8 || (bytes.len() == 8 && bytes[0] > 0x7F) {
+            let index = self.strings.push(bytes)?;
+            self.features |= let_module::features::BIG_INTEGERS;
+            self.string_operand(let_opcodes::BIGINT, index);
+            return Ok(());
+        }
+        let value = bytes.iter().fold(0, |value, &b| value << 8 | b as u64);
         if value <= u8::MAX as u64 {
             self.opcodes.extend(&[let_opcodes::INT1, value as u8]);
         } else if value <= 0xFFFF {
@@ -51,6 +74,87 @@ impl Emitter {
         Ok(())
     }
 
+    pub fn string(&mut self, value: &[u8]) -> let_result::Result {
+        let index = self.strings.push(value)?;
+        self.string_operand(let_opcodes::STR, index);
+        Ok(())
+    }
+
+    /// Emits `opcode` with string `index` operand.
+    fn string_operand(&mut self, opcode: u8, index: u32) {
+        self.opcodes.push(opcode);
+        self.relocations.push_string(self.opcodes.len() as u32);
+        self.opcodes.extend(&index.to_be_bytes());
+    }
+
+    /// Emits `opcode` with code `address` operand, returns address of
+    /// the operand.
+    fn address_operand(&mut self, opcode: u8, address: u32) -> u32 {
+        self.opcodes.push(opcode);
+        let operand = self.opcodes.len() as u32;
+        self.relocations.push_address(operand);
+        self.opcodes.extend(&address.to_be_bytes());
+        operand
+    }
+
+    /// Converts `parts` values on the stack to strings and joins them.
+    pub fn concat(&mut self, parts: u8) -> let_result::Result {
+        self.opcodes.extend(&[let_opcodes::CONCAT, parts]);
+        Ok(())
+    }
+
+    pub fn builtin(&mut self, name: &[u8], arguments: u8) -> let_result::Result {
+        let opcode = match name {
+            b"format" => let_opcodes::FORMAT,
+            _ => panic!("Unknown builtin {name:?}"),
+        };
+        self.opcodes.extend(&[opcode, arguments]);
+        Ok(())
+    }
+
+    /// Creates instance of class `name`.
+    pub fn new_instance(&mut self, name: &[u8]) -> let_result::Result {
+        let index = self.strings.push(name)?;
+        self.string_operand(let_opcodes::NEW, index);
+        Ok(())
+    }
+
+    pub fn get_field(&mut self, name: &[u8]) -> let_result::Result {
+        let index = self.strings.push(name)?;
+        self.string_operand(let_opcodes::GETF, index);
+        Ok(())
+    }
+
+    /// Checks at run time that value is of type `name`, value is kept.
+    pub fn check_type(&mut self, name: &[u8]) -> let_result::Result {
+        let index = self.strings.push(name)?;
+        self.features |= let_module::features::TYPE_CHECKS;
+        self.string_operand(let_opcodes::TYPE, index);
+        Ok(())
+    }
+
+    pub fn set_field(&mut self, name: &[u8]) -> let_result::Result {
+        let index = self.strings.push(name)?;
+        self.string_operand(let_opcodes::SETF, index);
+        Ok(())
+    }
+
+    /*
+       VOID or STR class ; Void for dynamic dispatch, class name for `super` call
+       ...               ; Object and other arguments
+       INVOKE method arguments
+    */
+    pub fn invoke(&mut self, name: &[u8], arguments: u8) -> let_result::Result {
+        let index = self.strings.push(name)?;
+        self.string_operand(let_opcodes::INVOKE, index);
+        self.opcodes.extend(&[arguments, 0, 0, 0]);
+        Ok(())
+    }
+
+    pub fn class(&mut self, class: let_module::ClassDescriptor) -> let_result::Result {
+        self.classes.push(class)
+    }
+
     pub fn call(&mut self, arguments: u8) -> let_result::Result {
         self.opcodes.extend(&[let_opcodes::CALL, arguments]);
         Ok(())
@@ -63,14 +167,42 @@ impl Emitter {
             b">  " => let_opcodes::GR,
             b"== " => let_opcodes::EQ,
             b"<= " => let_opcodes::LE,
+            b">= " => let_opcodes::GE,
+            b"!= " => let_opcodes::NE,
             b"-  " => let_opcodes::SUB,
             b"*  " => let_opcodes::MUL,
-            _ => panic!("Unknown operator {operator:?}"),
+            b"/  " => let_opcodes::DIV,
+            b"%  " => let_opcodes::REM,
+            b"<< " => let_opcodes::SHL,
+            b">> " => let_opcodes::SHR,
+            b".. " => let_opcodes::RANGE,
+            b"..=" => let_opcodes::IRANGE,
+            _ => {
+                return let_result::raise!(
+                    let_result::codes::UNSUPPORTED_OPERATOR,
+                    "Operator \"{}\" is not supported.",
+                    String::from_utf8_lossy(&operator).trim()
+                )
+            }
         };
+        if matches!(
+            opcode,
+            let_opcodes::DIV | let_opcodes::REM | let_opcodes::SHL | let_opcodes::SHR
+        ) {
+            self.features |= let_module::features::DIVISION_AND_SHIFTS;
+        }
         self.opcodes.extend(&[opcode]);
         Ok(())
     }
 
+    pub fn negate(&mut self) {
+        self.opcodes.push(let_opcodes::NEG);
+    }
+
+    pub fn not(&mut self) {
+        self.opcodes.push(let_opcodes::NOT);
+    }
+
     pub fn drop(&mut self) -> let_result::Result {
         self.opcodes.extend(&[let_opcodes::DROP]);
         Ok(())
@@ -87,16 +219,32 @@ impl Emitter {
     }
 
     pub fn jump(&mut self, id: u32) -> let_result::Result {
-        self.opcodes.extend(&[let_opcodes::JP]);
-        self.indexed_links.push(id, self.opcodes.len() as u32);
-        self.opcodes.extend(&[0, 0, 0, 0]);
+        let address = self.address_operand(let_opcodes::JP, 0);
+        self.indexed_links.push(id, address);
         Ok(())
     }
 
     pub fn jump_false(&mut self, id: u32) -> let_result::Result {
-        self.opcodes.extend(&[let_opcodes::JPF]);
-        self.indexed_links.push(id, self.opcodes.len() as u32);
-        self.opcodes.extend(&[0, 0, 0, 0]);
+        let address = self.address_operand(let_opcodes::JPF, 0);
+        self.indexed_links.push(id, address);
+        Ok(())
+    }
+
+    pub fn jump_false_keep(&mut self, id: u32) -> let_result::Result {
+        let address = self.address_operand(let_opcodes::JPFK, 0);
+        self.indexed_links.push(id, address);
+        Ok(())
+    }
+
+    pub fn jump_void(&mut self, id: u32) -> let_result::Result {
+        let address = self.address_operand(let_opcodes::JPV, 0);
+        self.indexed_links.push(id, address);
+        Ok(())
+    }
+
+    pub fn jump_not_void(&mut self, id: u32) -> let_result::Result {
+        let address = self.address_operand(let_opcodes::JPNV, 0);
+        self.indexed_links.push(id, address);
         Ok(())
     }
 
@@ -112,29 +260,111 @@ impl Emitter {
         }
     }
 
-    pub fn pointer(&mut self, name: &[u8]) -> let_result::Result {
-        self.opcodes.extend(&[let_opcodes::PTR]);
-        self.named_links.push(name, self.opcodes.len() as u32);
-        self.opcodes.extend(&[0, 0, 0, 0]);
+    /// Pointer to named label, `range` is location of the reference
+    /// in source file.
+    pub fn pointer(&mut self, name: &[u8], range: std::ops::Range<usize>) -> let_result::Result {
+        let link = let_module::NamedLink {
+            address: self.address_operand(let_opcodes::PTR, 0),
+            location: self.location(range),
+        };
+        self.named_links.push(name, link);
         Ok(())
     }
 
+    fn location(&self, range: std::ops::Range<usize>) -> Option<let_module::Location> {
+        self.source.map(|source| let_module::Location {
+            source,
+            start: range.start as u32,
+            end: range.end as u32,
+        })
+    }
+
+    /// Pointer to function which is called directly with `arguments`,
+    /// `range` is location of the call in source file.
+    pub fn call_pointer(
+        &mut self,
+        name: &[u8],
+        arguments: u8,
+        range: std::ops::Range<usize>,
+    ) -> let_result::Result {
+        self.calls.push(let_module::Call {
+            name: Vec::from(name).into_boxed_slice(),
+            arguments,
+            location: self.location(range.clone()),
+        });
+        self.pointer(name, range)
+    }
+
+    pub fn pointer_label(&mut self, id: u32) -> let_result::Result {
+        let address = self.address_operand(let_opcodes::PTR, 0);
+        self.indexed_links.push(id, address);
+        Ok(())
+    }
+
+    /// Binds function pointer on the stack to the frame `depth` levels up.
+    /// Returns address of depth operand.
+    pub fn closure(&mut self, depth: u8) -> let_result::Result<u32> {
+        self.opcodes.extend(&[let_opcodes::CLOS, depth]);
+        Ok(self.opcodes.len() as u32 - 1)
+    }
+
+    pub fn load_outer(&mut self, depth: u8, index: u8) {
+        self.opcodes.extend(&[let_opcodes::LDU, depth, index]);
+    }
+
+    pub fn store_outer(&mut self, depth: u8, index: u8) {
+        self.opcodes.extend(&[let_opcodes::STU, depth, index]);
+    }
+
     pub fn ret(&mut self) -> let_result::Result {
         self.opcodes.extend(&[let_opcodes::RET]);
         Ok(())
     }
 
-    /*
-    
-    function_name:
-        DB 3  ; Arguments count
-        DD 23 ; Stack size
-        ... ; Other opcodes
-     */
-    pub fn function(&mut self, args_count: u8) -> let_result::Result<u32> {
-        let address = self.opcodes.len() + 1;
-        self.opcodes.extend(&[args_count, 0, 0, 0, 0]);
-        Ok(address as u32)
+    /// Starts function `name` which code follows, `range` is location
+    /// of its declaration. Returns index of the function for
+    /// `function_end`.
+    pub fn function(
+        &mut self,
+        name: &[u8],
+        arguments: u8,
+        range: std::ops::Range<usize>,
+    ) -> let_result::Result<u32> {
+        Ok(self.functions.push(let_module::Function {
+            name: Vec::from(name).into_boxed_slice(),
+            entry: self.offset(),
+            end: self.offset(),
+            arguments,
+            stack_size: 0,
+            location: self.location(range),
+        }))
+    }
+
+    /// Ends code of function `index` which has `stack_size` locals
+    /// beyond arguments.
+    pub fn function_end(&mut self, index: u32, stack_size: u32) {
+        let end = self.offset();
+        let function = self.functions.get_mut(index).unwrap();
+        function.end = end;
+        function.stack_size = stack_size;
+    }
+
+    /// Records arguments count of function or constructor `name`.
+    pub fn signature(&mut self, name: &[u8], arguments: u8) {
+        self.signatures.push(name, arguments);
+    }
+
+    /// Arguments count of emitted or imported function or constructor.
+    pub fn arguments(&self, name: &[u8]) -> Option<u8> {
+        self.signatures
+            .get(name)
+            .or_else(|| self.imported.get(name))
+    }
+
+    /// Sets path of source file, locations of calls refer to it.
+    pub fn set_source(&mut self, path: &str) -> let_result::Result {
+        self.source = Some(self.strings.push(path.as_bytes())?);
+        Ok(())
     }
 
     pub fn set(&mut self, address: u32, value: u8) {
@@ -158,14 +388,26 @@ impl Emitter {
     }
 
     pub fn jump_to(&mut self, address: u32) {
-        self.opcodes.push(let_opcodes::JP);
-        self.opcodes.extend(&address.to_be_bytes());
+        self.address_operand(let_opcodes::JP, address);
     }
 
     pub fn void(&mut self) {
         self.opcodes.push(let_opcodes::VOID);
     }
 
+    pub fn swap(&mut self) {
+        self.opcodes.push(let_opcodes::SWAP);
+    }
+
+    pub fn dup(&mut self) {
+        self.opcodes.push(let_opcodes::DUP);
+    }
+
+    /// Moves top value under two values below it.
+    pub fn rotate(&mut self) {
+        self.opcodes.push(let_opcodes::ROT);
+    }
+
     pub fn list(&mut self) {
         self.opcodes.push(let_opcodes::LIST);
     }
@@ -184,14 +426,51 @@ impl Emitter {
         self.indexed_labels.clear();
         self.named_links
             .resolve(&self.named_labels, &mut self.opcodes)?;
-        Ok(())
+        self.calls.check(&self.signatures, &self.strings)?;
+        self.calls.check(&self.imported, &self.strings)
+    }
+
+    /// Reports unresolved links to emitted or imported modules. Modules
+    /// which are neither emitted nor imported may be compiled later.
+    pub fn undefined(&self) -> Vec<let_result::Diagnostic> {
+        let modules = self.named_labels.modules();
+        let imported = self.imported.modules();
+        self.named_links.check_undefined(&self.strings, |name| {
+            let module = let_module::module_name(name);
+            modules.contains(module)
+                || imported.contains(module) && self.imported.get(name).is_none()
+        })
+    }
+
+    /// Reports every unresolved link, emitted modules are complete program.
+    pub fn check_undefined(&self) -> Vec<let_result::Diagnostic> {
+        self.named_links.check_undefined(&self.strings, |_| true)
+    }
+
+    /// Adds signatures of other module, calls of its functions are checked.
+    pub fn import(&mut self, interface: let_module::Interface) {
+        self.imported.merge(interface.signatures);
+    }
+
+    /// Interface of emitted modules.
+    pub fn interface(&self) -> let_module::Interface {
+        let_module::Interface {
+            signatures: self.signatures.clone(),
+        }
     }
 
     pub fn into_module(self) -> let_module::Module {
         let_module::Module {
             opcodes: self.opcodes,
+            relocations: self.relocations,
+            features: self.features,
             labels: self.named_labels,
             links: self.named_links,
+            strings: self.strings,
+            classes: self.classes,
+            signatures: self.signatures,
+            calls: self.calls,
+            functions: self.functions,
         }
     }
 
@@ -207,7 +486,11 @@ impl Emitter {
         match std::fs::File::create(path) {
             Ok(mut file) => self.write(&mut file)?,
             Err(error) => {
-                return let_result::raise!("Unable to create file {:?}, error: {error}.", path)
+                return let_result::raise!(
+                    let_result::codes::IO,
+                    "Unable to create file {:?}, error: {error}.",
+                    path
+                )
             }
         }
         Ok(())
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{hash_map::Entry, HashMap, HashSet},
     fmt,
     hash::Hash,
     io::{Read, Write},
@@ -21,22 +21,35 @@ impl<'a> fmt::Display for U8Str<'a> {
 
 mod utils;
 
-fn resolve<T>(
-    links: &mut HashMap<T, Vec<u32>>,
+/// Link is the address of operand which is set to label address.
+trait Link {
+    fn address(&self) -> u32;
+}
+
+impl Link for u32 {
+    fn address(&self) -> u32 {
+        *self
+    }
+}
+
+fn resolve<T, L>(
+    links: &mut HashMap<T, Vec<L>>,
     labels: &HashMap<T, u32>,
     opcodes: &mut [u8],
 ) -> let_result::Result
 where
     T: Hash + PartialEq + Eq,
+    L: Link,
 {
     for (name, &address) in labels.iter() {
         if let Some(links) = links.get_mut(name) {
-            for link in links.iter().cloned() {
+            for link in links.iter() {
                 for (i, b) in address.to_be_bytes().iter().cloned().enumerate() {
-                    match opcodes.get_mut(link as usize + i) {
+                    match opcodes.get_mut(link.address() as usize + i) {
                         Some(r) => *r = b,
                         None => {
                             return let_result::raise!(
+                                let_result::codes::CORRUPTED_MODULE,
                                 "Unable to resolve links, bytecode is corrupted."
                             )
                         }
@@ -50,19 +63,37 @@ where
     Ok(())
 }
 
+/// Module of label like `module.name`.
+pub fn module_name(label: &[u8]) -> &[u8] {
+    label.split(|&c| c == b'.').next().unwrap()
+}
+
 pub struct NamedLabels(HashMap<Box<[u8]>, u32>);
 
+impl Default for NamedLabels {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NamedLabels {
     pub fn new() -> Self {
         Self(HashMap::new())
     }
 
     pub fn push(&mut self, name: Box<[u8]>, address: u32) -> let_result::Result {
-        if self.0.contains_key(&name) {
-            let_result::raise!("Label \"{}\" already exists.", U8Str(&name))
-        } else {
-            self.0.insert(name, address);
-            Ok(())
+        match self.0.entry(name) {
+            Entry::Occupied(e) => {
+                let_result::raise!(
+                    let_result::codes::DUPLICATE_LABEL,
+                    "Label \"{}\" already exists.",
+                    U8Str(e.key())
+                )
+            }
+            Entry::Vacant(e) => {
+                e.insert(address);
+                Ok(())
+            }
         }
     }
 
@@ -88,7 +119,7 @@ impl NamedLabels {
         for _ in 0..len {
             let name = utils::read_label(read)?;
             if result.contains_key(&name) {
-                return let_result::raise!("labels conflict.");
+                return let_result::raise!(let_result::codes::CORRUPTED_MODULE, "labels conflict.");
             }
             let address = utils::read_u32(read)?;
             result.insert(name, address);
@@ -101,7 +132,11 @@ impl NamedLabels {
 
         for (name, address) in other.0 {
             if self.0.contains_key(&name) {
-                return let_result::raise!("Duplicate label \"{}\".", U8Str(&name));
+                return let_result::raise!(
+                    let_result::codes::DUPLICATE_LABEL,
+                    "Duplicate label \"{}\".",
+                    U8Str(&name)
+                );
             }
             self.0.insert(name, address + offset);
         }
@@ -112,21 +147,82 @@ impl NamedLabels {
     pub fn get(&self, name: &[u8]) -> Option<u32> {
         self.0.get(name).cloned()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], u32)> {
+        self.0
+            .iter()
+            .map(|(name, &address)| (name.as_ref(), address))
+    }
+
+    /// Names of modules which have labels.
+    pub fn modules(&self) -> HashSet<&[u8]> {
+        self.0.keys().map(|label| module_name(label)).collect()
+    }
 }
 
-pub struct NamedLinks(HashMap<Box<[u8]>, Vec<u32>>);
+/// Reference to named label from operand at `address`.
+pub struct NamedLink {
+    pub address: u32,
+    pub location: Option<Location>,
+}
+
+impl Link for NamedLink {
+    fn address(&self) -> u32 {
+        self.address
+    }
+}
+
+/// Diagnostic of undefined `name` located at the first of its `links`,
+/// other references of the same file are labeled.
+fn undefined_symbol(name: &[u8], links: &[NamedLink], strings: &Strings) -> let_result::Diagnostic {
+    let diagnostic = let_result::Diagnostic::error(
+        let_result::codes::UNDEFINED_SYMBOL,
+        format!("Undefined symbol \"{}\".", U8Str(name)),
+    );
+    let mut locations: Vec<Location> = links.iter().filter_map(|link| link.location).collect();
+    locations.sort_by_key(|location| (location.source, location.start));
+    let first = match locations.first() {
+        Some(&first) => first,
+        None => return diagnostic,
+    };
+    let mut diagnostic = first.locate(diagnostic, strings);
+    for location in &locations[1..] {
+        if location.source == first.source && location.start != first.start {
+            diagnostic = diagnostic.with_label(
+                location.start as usize..location.end as usize,
+                "Also referenced here.".to_string(),
+            );
+        } else if location.source != first.source {
+            if let Some(path) = strings.get(location.source) {
+                diagnostic = diagnostic.with_note(format!(
+                    "Also referenced in \"{}\".",
+                    String::from_utf8_lossy(path)
+                ));
+            }
+        }
+    }
+    diagnostic
+}
+
+pub struct NamedLinks(HashMap<Box<[u8]>, Vec<NamedLink>>);
+
+impl Default for NamedLinks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl NamedLinks {
     pub fn new() -> Self {
         Self(HashMap::new())
     }
 
-    pub fn push(&mut self, name: &[u8], address: u32) {
+    pub fn push(&mut self, name: &[u8], link: NamedLink) {
         if let Some(links) = self.0.get_mut(name) {
-            links.push(address);
+            links.push(link);
         } else {
             self.0
-                .insert(Vec::from(name).into_boxed_slice(), vec![address]);
+                .insert(Vec::from(name).into_boxed_slice(), vec![link]);
         }
     }
 
@@ -134,6 +230,21 @@ impl NamedLinks {
         resolve(&mut self.0, &labels.0, opcodes)
     }
 
+    /// Reports every unresolved link which `undefined` accepts,
+    /// other links may be resolved by modules linked later. Names are
+    /// sorted, so diagnostics are stable.
+    pub fn check_undefined<F>(&self, strings: &Strings, undefined: F) -> Vec<let_result::Diagnostic>
+    where
+        F: Fn(&[u8]) -> bool,
+    {
+        let mut names: Vec<_> = self.0.iter().filter(|(name, _)| undefined(name)).collect();
+        names.sort_by_key(|(name, _)| *name);
+        names
+            .into_iter()
+            .map(|(name, links)| undefined_symbol(name, links, strings))
+            .collect()
+    }
+
     pub fn write<W>(&self, write: &mut W) -> let_result::Result
     where
         W: Write,
@@ -142,7 +253,12 @@ impl NamedLinks {
         utils::write_u32(write, self.0.len() as u32)?;
         for (k, v) in self.0.iter() {
             utils::write_label(write, k)?;
-            utils::write_u32_slice(write, v)?;
+            debug_assert!(v.len() <= u32::MAX as usize);
+            utils::write_u32(write, v.len() as u32)?;
+            for link in v {
+                utils::write_u32(write, link.address)?;
+                Location::write_option(write, link.location)?;
+            }
         }
         Ok(())
     }
@@ -156,18 +272,29 @@ impl NamedLinks {
         for _ in 0..len {
             let name = utils::read_label(read)?;
             if result.contains_key(&name) {
-                return let_result::raise!("labels conflict.");
+                return let_result::raise!(let_result::codes::CORRUPTED_MODULE, "labels conflict.");
+            }
+            let count = utils::read_u32(read)?;
+            let mut links = Vec::new();
+            for _ in 0..count {
+                links.push(NamedLink {
+                    address: utils::read_u32(read)?,
+                    location: Location::read_option(read)?,
+                });
             }
-            let links = utils::read_u32_vec(read)?;
             result.insert(name, links);
         }
         Ok(Self(result))
     }
 
-    pub fn merge(&mut self, other: Self, offset: u32) {
+    /// Addresses and path indices of other module are shifted.
+    pub fn merge(&mut self, other: Self, offset: u32, strings_offset: u32) {
         for (name, mut src_links) in other.0 {
             for link in src_links.iter_mut() {
-                *link = *link + offset;
+                link.address += offset;
+                if let Some(location) = &mut link.location {
+                    location.source += strings_offset;
+                }
             }
             if let Some(links) = self.0.get_mut(&name) {
                 links.extend(src_links);
@@ -180,17 +307,26 @@ impl NamedLinks {
 
 pub struct IndexedLabels(HashMap<u32, u32>);
 
+impl Default for IndexedLabels {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl IndexedLabels {
     pub fn new() -> Self {
         Self(HashMap::new())
     }
 
     pub fn push(&mut self, index: u32, address: u32) -> let_result::Result {
-        if self.0.contains_key(&index) {
-            let_result::raise!("Label {index} already exists.")
-        } else {
-            self.0.insert(index, address);
+        if let Entry::Vacant(e) = self.0.entry(index) {
+            e.insert(address);
             Ok(())
+        } else {
+            let_result::raise!(
+                let_result::codes::DUPLICATE_LABEL,
+                "Label {index} already exists."
+            )
         }
     }
 
@@ -201,6 +337,12 @@ impl IndexedLabels {
 
 pub struct IndexedLinks(HashMap<u32, Vec<u32>>);
 
+impl Default for IndexedLinks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl IndexedLinks {
     pub fn new() -> Self {
         Self(HashMap::new())
@@ -223,25 +365,833 @@ impl IndexedLinks {
     }
 }
 
+/// String constants, opcodes refer to them by index.
+pub struct Strings {
+    list: Vec<Box<[u8]>>,
+    indices: HashMap<Box<[u8]>, u32>,
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Strings {
+    pub fn new() -> Self {
+        Self {
+            list: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+
+    /// Returns index of string, adds the string if it is new.
+    pub fn push(&mut self, value: &[u8]) -> let_result::Result<u32> {
+        if let Some(index) = self.indices.get(value) {
+            return Ok(*index);
+        }
+        if self.list.len() >= u32::MAX as usize {
+            return let_result::raise!(
+                let_result::codes::TOO_MANY_STRINGS,
+                "Too many string constants."
+            );
+        }
+        let index = self.list.len() as u32;
+        let value = Vec::from(value).into_boxed_slice();
+        self.indices.insert(value.clone(), index);
+        self.list.push(value);
+        Ok(index)
+    }
+
+    pub fn get(&self, index: u32) -> Option<&[u8]> {
+        self.list.get(index as usize).map(|s| s.as_ref())
+    }
+
+    pub fn as_slice(&self) -> &[Box<[u8]>] {
+        &self.list
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn write<W>(&self, write: &mut W) -> let_result::Result
+    where
+        W: Write,
+    {
+        debug_assert!(self.list.len() <= u32::MAX as usize);
+        utils::write_u32(write, self.list.len() as u32)?;
+        for value in self.list.iter() {
+            utils::write_u8_slice(write, value)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R>(read: &mut R) -> let_result::Result<Self>
+    where
+        R: Read,
+    {
+        let len = utils::read_u32(read)?;
+        let mut result = Self::new();
+        for _ in 0..len {
+            let value = utils::read_u8_vec(read)?.into_boxed_slice();
+            result
+                .indices
+                .entry(value.clone())
+                .or_insert(result.list.len() as u32);
+            result.list.push(value);
+        }
+        Ok(result)
+    }
+
+    /// Appends other strings without deduplication, so indices of
+    /// other module are shifted by length of this list.
+    pub fn merge(&mut self, other: Self) -> let_result::Result {
+        if self.list.len() + other.list.len() > u32::MAX as usize {
+            return let_result::raise!(
+                let_result::codes::TOO_MANY_STRINGS,
+                "Too many string constants."
+            );
+        }
+        for value in other.list {
+            self.indices
+                .entry(value.clone())
+                .or_insert(self.list.len() as u32);
+            self.list.push(value);
+        }
+        Ok(())
+    }
+}
+
+/// Addresses of absolute operands, they are shifted when modules are
+/// merged, so code is never decoded. Operand is 4 bytes big-endian.
+pub struct Relocations {
+    /// Operands with code address like `JP` and `PTR` ones.
+    addresses: Vec<u32>,
+    /// Operands with string index like `STR` and `INVOKE` ones.
+    strings: Vec<u32>,
+}
+
+impl Default for Relocations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn relocate(opcodes: &mut [u8], operands: &[u32], offset: u32) -> let_result::Result {
+    for &operand in operands {
+        let operand = operand as usize;
+        let relocated = opcodes.get_mut(operand..operand + 4).and_then(|bytes| {
+            let value = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            bytes.copy_from_slice(&value.checked_add(offset)?.to_be_bytes());
+            Some(())
+        });
+        if relocated.is_none() {
+            return let_result::raise!(
+                let_result::codes::CORRUPTED_MODULE,
+                "Unable to relocate operand, bytecode is corrupted."
+            );
+        }
+    }
+    Ok(())
+}
+
+impl Relocations {
+    pub fn new() -> Self {
+        Self {
+            addresses: Vec::new(),
+            strings: Vec::new(),
+        }
+    }
+
+    /// Operand at `address` is code address.
+    pub fn push_address(&mut self, address: u32) {
+        self.addresses.push(address);
+    }
+
+    /// Operand at `address` is string index.
+    pub fn push_string(&mut self, address: u32) {
+        self.strings.push(address);
+    }
+
+    /// Shifts operands of `opcodes` which are placed at `offset`
+    /// after strings placed at `strings_offset`.
+    pub fn relocate(
+        &self,
+        opcodes: &mut [u8],
+        offset: u32,
+        strings_offset: u32,
+    ) -> let_result::Result {
+        relocate(opcodes, &self.addresses, offset)?;
+        relocate(opcodes, &self.strings, strings_offset)
+    }
+
+    pub fn write<W>(&self, write: &mut W) -> let_result::Result
+    where
+        W: Write,
+    {
+        utils::write_u32_slice(write, &self.addresses)?;
+        utils::write_u32_slice(write, &self.strings)
+    }
+
+    pub fn read<R>(read: &mut R) -> let_result::Result<Self>
+    where
+        R: Read,
+    {
+        Ok(Self {
+            addresses: utils::read_u32_vec(read)?,
+            strings: utils::read_u32_vec(read)?,
+        })
+    }
+
+    pub fn merge(&mut self, other: Self, offset: u32) {
+        self.addresses
+            .extend(other.addresses.iter().map(|address| address + offset));
+        self.strings
+            .extend(other.strings.iter().map(|address| address + offset));
+    }
+}
+
+/// Class declared in module. Methods are named labels `class.method`,
+/// so their addresses are relocated together with other labels.
+pub struct ClassDescriptor {
+    pub name: Box<[u8]>,
+    pub parent: Option<Box<[u8]>>,
+    pub methods: Vec<Box<[u8]>>,
+}
+
+impl ClassDescriptor {
+    fn write<W>(&self, write: &mut W) -> let_result::Result
+    where
+        W: Write,
+    {
+        utils::write_label(write, &self.name)?;
+        match &self.parent {
+            Some(parent) => {
+                utils::write_u8(write, 1)?;
+                utils::write_label(write, parent)?;
+            }
+            None => utils::write_u8(write, 0)?,
+        }
+        debug_assert!(self.methods.len() <= u32::MAX as usize);
+        utils::write_u32(write, self.methods.len() as u32)?;
+        for method in self.methods.iter() {
+            utils::write_label(write, method)?;
+        }
+        Ok(())
+    }
+
+    fn read<R>(read: &mut R) -> let_result::Result<Self>
+    where
+        R: Read,
+    {
+        let name = utils::read_label(read)?;
+        let parent = match utils::read_u8(read)? {
+            0 => None,
+            1 => Some(utils::read_label(read)?),
+            _ => {
+                return let_result::raise!(
+                    let_result::codes::CORRUPTED_MODULE,
+                    "Class descriptor is corrupted."
+                )
+            }
+        };
+        let len = utils::read_u32(read)?;
+        let mut methods = Vec::new();
+        for _ in 0..len {
+            methods.push(utils::read_label(read)?);
+        }
+        Ok(Self {
+            name,
+            parent,
+            methods,
+        })
+    }
+}
+
+pub struct Classes(Vec<ClassDescriptor>);
+
+impl Default for Classes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Classes {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn push(&mut self, class: ClassDescriptor) -> let_result::Result {
+        if self.get(&class.name).is_some() {
+            return let_result::raise!(
+                let_result::codes::DUPLICATE_CLASS,
+                "Class {} already exists",
+                U8Str(&class.name)
+            );
+        }
+        self.0.push(class);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &[u8]) -> Option<&ClassDescriptor> {
+        self.0.iter().find(|class| class.name.as_ref() == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ClassDescriptor> {
+        self.0.iter()
+    }
+
+    pub fn write<W>(&self, write: &mut W) -> let_result::Result
+    where
+        W: Write,
+    {
+        debug_assert!(self.0.len() <= u32::MAX as usize);
+        utils::write_u32(write, self.0.len() as u32)?;
+        for class in self.0.iter() {
+            class.write(write)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R>(read: &mut R) -> let_result::Result<Self>
+    where
+        R: Read,
+    {
+        let len = utils::read_u32(read)?;
+        let mut result = Self::new();
+        for _ in 0..len {
+            result.push(ClassDescriptor::read(read)?)?;
+        }
+        Ok(result)
+    }
+
+    pub fn merge(&mut self, other: Self) -> let_result::Result {
+        for class in other.0 {
+            self.push(class)?;
+        }
+        Ok(())
+    }
+}
+
+/// Arguments count of functions and constructors by label.
+#[derive(Clone)]
+pub struct Signatures(HashMap<Box<[u8]>, u8>);
+
+impl Default for Signatures {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Signatures {
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    pub fn push(&mut self, name: &[u8], arguments: u8) {
+        self.0.insert(Vec::from(name).into_boxed_slice(), arguments);
+    }
+
+    pub fn get(&self, name: &[u8]) -> Option<u8> {
+        self.0.get(name).cloned()
+    }
+
+    /// Names of modules which have signatures.
+    pub fn modules(&self) -> HashSet<&[u8]> {
+        self.0.keys().map(|name| module_name(name)).collect()
+    }
+
+    pub fn write<W>(&self, write: &mut W) -> let_result::Result
+    where
+        W: Write,
+    {
+        debug_assert!(self.0.len() <= u32::MAX as usize);
+        utils::write_u32(write, self.0.len() as u32)?;
+        for (name, arguments) in self.0.iter() {
+            utils::write_label(write, name)?;
+            utils::write_u8(write, *arguments)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R>(read: &mut R) -> let_result::Result<Self>
+    where
+        R: Read,
+    {
+        let len = utils::read_u32(read)?;
+        let mut result = Self::new();
+        for _ in 0..len {
+            let name = utils::read_label(read)?;
+            let arguments = utils::read_u8(read)?;
+            result.0.insert(name, arguments);
+        }
+        Ok(result)
+    }
+
+    /// Labels are unique, so signatures never conflict.
+    pub fn merge(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+}
+
+/// Byte range of source file, path of the file is a string constant.
+#[derive(Clone, Copy)]
+pub struct Location {
+    pub source: u32,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Location {
+    /// Sets location of diagnostic to this one.
+    pub fn locate(
+        &self,
+        diagnostic: let_result::Diagnostic,
+        strings: &Strings,
+    ) -> let_result::Diagnostic {
+        let diagnostic = diagnostic.with_range(self.start as usize..self.end as usize);
+        match strings.get(self.source) {
+            Some(path) => diagnostic.with_file(String::from_utf8_lossy(path).into_owned()),
+            None => diagnostic,
+        }
+    }
+
+    fn write_option<W>(write: &mut W, location: Option<Location>) -> let_result::Result
+    where
+        W: Write,
+    {
+        match location {
+            Some(location) => {
+                utils::write_u8(write, 1)?;
+                utils::write_u32(write, location.source)?;
+                utils::write_u32(write, location.start)?;
+                utils::write_u32(write, location.end)?;
+            }
+            None => utils::write_u8(write, 0)?,
+        }
+        Ok(())
+    }
+
+    fn read_option<R>(read: &mut R) -> let_result::Result<Option<Location>>
+    where
+        R: Read,
+    {
+        match utils::read_u8(read)? {
+            0 => Ok(None),
+            1 => Ok(Some(Location {
+                source: utils::read_u32(read)?,
+                start: utils::read_u32(read)?,
+                end: utils::read_u32(read)?,
+            })),
+            _ => let_result::raise!(
+                let_result::codes::CORRUPTED_MODULE,
+                "Location is corrupted."
+            ),
+        }
+    }
+}
+
+/// Direct call of function by its label.
+pub struct Call {
+    pub name: Box<[u8]>,
+    pub arguments: u8,
+    pub location: Option<Location>,
+}
+
+/// Calls which arguments count is not checked yet, since their functions
+/// are declared in other modules.
+pub struct Calls(Vec<Call>);
+
+impl Default for Calls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Calls {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn push(&mut self, call: Call) {
+        self.0.push(call);
+    }
+
+    /// Checks calls of functions with known signatures and keeps the rest.
+    pub fn check(&mut self, signatures: &Signatures, strings: &Strings) -> let_result::Result {
+        for call in &self.0 {
+            let expected = match signatures.get(&call.name) {
+                Some(expected) if expected != call.arguments => expected,
+                _ => continue,
+            };
+            let mut diagnostic = let_result::Diagnostic::error(
+                let_result::codes::ARITY_MISMATCH,
+                format!(
+                    "Function \"{}\" expects {expected} argument{}, found {}.",
+                    U8Str(&call.name),
+                    if expected == 1 { "" } else { "s" },
+                    call.arguments
+                ),
+            );
+            if let Some(location) = call.location {
+                diagnostic = location.locate(diagnostic, strings);
+            }
+            return Err(diagnostic.into());
+        }
+        self.0.retain(|call| signatures.get(&call.name).is_none());
+        Ok(())
+    }
+
+    pub fn write<W>(&self, write: &mut W) -> let_result::Result
+    where
+        W: Write,
+    {
+        debug_assert!(self.0.len() <= u32::MAX as usize);
+        utils::write_u32(write, self.0.len() as u32)?;
+        for call in self.0.iter() {
+            utils::write_label(write, &call.name)?;
+            utils::write_u8(write, call.arguments)?;
+            Location::write_option(write, call.location)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R>(read: &mut R) -> let_result::Result<Self>
+    where
+        R: Read,
+    {
+        let len = utils::read_u32(read)?;
+        let mut result = Self::new();
+        for _ in 0..len {
+            let name = utils::read_label(read)?;
+            let arguments = utils::read_u8(read)?;
+            let location = Location::read_option(read)?;
+            result.push(Call {
+                name,
+                arguments,
+                location,
+            });
+        }
+        Ok(result)
+    }
+
+    /// Path indices of other module are shifted like its strings.
+    pub fn merge(&mut self, other: Self, strings_offset: u32) {
+        for mut call in other.0 {
+            if let Some(location) = &mut call.location {
+                location.source += strings_offset;
+            }
+            self.0.push(call);
+        }
+    }
+}
+
+/// Declarations exported by module, so modules using it are checked
+/// without its source.
+#[derive(Clone)]
+pub struct Interface {
+    pub signatures: Signatures,
+}
+
+impl Interface {
+    pub fn write<W>(&self, mut write: W) -> let_result::Result
+    where
+        W: std::io::Write,
+    {
+        write.write_all(b"LETI")?;
+        self.signatures.write(&mut write)
+    }
+
+    pub fn read<R>(mut read: R) -> let_result::Result<Self>
+    where
+        R: std::io::Read,
+    {
+        let mut magic = [0u8; 4];
+        read.read_exact(&mut magic)?;
+        if &magic != b"LETI" {
+            return let_result::raise!(
+                let_result::codes::UNKNOWN_FORMAT,
+                "Unknown interface format."
+            );
+        }
+        Ok(Self {
+            signatures: Signatures::read(&mut read)?,
+        })
+    }
+}
+
+/// Function of module, its code is `entry..end` range of opcodes.
+/// Code of local function is nested in code of enclosing one.
+#[derive(Clone)]
+pub struct Function {
+    /// Label like `module.name`, local function is named after
+    /// enclosing one like `module.name.local`.
+    pub name: Box<[u8]>,
+    pub entry: u32,
+    pub end: u32,
+    pub arguments: u8,
+    /// Count of locals beyond arguments.
+    pub stack_size: u32,
+    pub location: Option<Location>,
+}
+
+impl Function {
+    /// Names function in stack trace with line of its declaration,
+    /// source file is loaded into `sources` if it is found.
+    pub fn describe(&self, strings: &Strings, sources: &mut let_result::SourceMap) -> String {
+        let name = U8Str(&self.name);
+        let location = self
+            .location
+            .and_then(|location| Some((strings.get(location.source)?, location.start)));
+        let (path, start) = match location {
+            Some((path, start)) => (String::from_utf8_lossy(path), start as usize),
+            None => return format!("in {name}"),
+        };
+        let file = sources.find(&path).map_or_else(|| sources.load(&path), Ok);
+        match file {
+            Ok(file) => {
+                let line = sources.get(file).line_number(start);
+                format!("in {name}, file \"{path}\", line: {line}")
+            }
+            Err(_) => format!("in {name}, file \"{path}\""),
+        }
+    }
+}
+
+/// Functions in order of their entries.
+pub struct Functions(Vec<Function>);
+
+impl Default for Functions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Functions {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Adds function, entries must increase. Returns index of function.
+    pub fn push(&mut self, function: Function) -> u32 {
+        debug_assert!(self.0.last().is_none_or(|last| last.entry < function.entry));
+        self.0.push(function);
+        self.0.len() as u32 - 1
+    }
+
+    pub fn get_mut(&mut self, index: u32) -> Option<&mut Function> {
+        self.0.get_mut(index as usize)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Function> {
+        self.0.iter()
+    }
+
+    /// Finds function which code starts at `entry`.
+    pub fn find(&self, entry: u32) -> Option<&Function> {
+        self.0
+            .binary_search_by_key(&entry, |function| function.entry)
+            .ok()
+            .map(|index| &self.0[index])
+    }
+
+    /// Finds the innermost function which code contains `address`.
+    pub fn containing(&self, address: u32) -> Option<&Function> {
+        let count = self.0.partition_point(|function| function.entry <= address);
+        self.0[..count]
+            .iter()
+            .rev()
+            .find(|function| address < function.end)
+    }
+
+    pub fn write<W>(&self, write: &mut W) -> let_result::Result
+    where
+        W: Write,
+    {
+        debug_assert!(self.0.len() <= u32::MAX as usize);
+        utils::write_u32(write, self.0.len() as u32)?;
+        for function in self.0.iter() {
+            utils::write_u8_slice(write, &function.name)?;
+            utils::write_u32(write, function.entry)?;
+            utils::write_u32(write, function.end)?;
+            utils::write_u8(write, function.arguments)?;
+            utils::write_u32(write, function.stack_size)?;
+            Location::write_option(write, function.location)?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R>(read: &mut R) -> let_result::Result<Self>
+    where
+        R: Read,
+    {
+        let len = utils::read_u32(read)?;
+        let mut result = Vec::new();
+        for _ in 0..len {
+            let function = Function {
+                name: utils::read_u8_vec(read)?.into_boxed_slice(),
+                entry: utils::read_u32(read)?,
+                end: utils::read_u32(read)?,
+                arguments: utils::read_u8(read)?,
+                stack_size: utils::read_u32(read)?,
+                location: Location::read_option(read)?,
+            };
+            let ordered = result
+                .last()
+                .is_none_or(|last: &Function| last.entry < function.entry);
+            if !ordered || function.end < function.entry {
+                return let_result::raise!(
+                    let_result::codes::CORRUPTED_MODULE,
+                    "Function table is corrupted."
+                );
+            }
+            result.push(function);
+        }
+        Ok(Self(result))
+    }
+
+    /// Code and path indices of other module are shifted.
+    pub fn merge(&mut self, other: Self, offset: u32, strings_offset: u32) {
+        for mut function in other.0 {
+            function.entry += offset;
+            function.end += offset;
+            if let Some(location) = &mut function.location {
+                location.source += strings_offset;
+            }
+            self.0.push(function);
+        }
+    }
+}
+
+/// Version of module format, modules of other versions are rejected.
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Opcode extensions, module records the ones its code uses, so
+/// runtime without them rejects the module instead of misreading it.
+pub mod features {
+    /// `BIGINT` opcode.
+    pub const BIG_INTEGERS: u32 = 1 << 0;
+    /// `TYPE` opcode.
+    pub const TYPE_CHECKS: u32 = 1 << 1;
+    /// `DIV`, `REM`, `SHL` and `SHR` opcodes.
+    pub const DIVISION_AND_SHIFTS: u32 = 1 << 2;
+
+    /// Extensions known to this version.
+    pub const SUPPORTED: u32 = BIG_INTEGERS | TYPE_CHECKS | DIVISION_AND_SHIFTS;
+}
+
+/// Sections of module body in order.
+const SECTIONS: [&str; 9] = [
+    "code",
+    "relocations",
+    "labels",
+    "links",
+    "strings",
+    "classes",
+    "signatures",
+    "calls",
+    "functions",
+];
+
+/// Reads section which must take exactly `data`.
+fn section<'a, T, F>(data: &'a [u8], name: &str, read: F) -> let_result::Result<T>
+where
+    F: FnOnce(&mut &'a [u8]) -> let_result::Result<T>,
+{
+    let mut rest = data;
+    match read(&mut rest) {
+        Ok(value) if rest.is_empty() => Ok(value),
+        Ok(_) | Err(let_result::Error::IO(_)) => let_result::raise!(
+            let_result::codes::CORRUPTED_MODULE,
+            "Section \"{name}\" of module is corrupted."
+        ),
+        Err(error) => Err(error),
+    }
+}
+
+/*
+Module file:
+    "LET&"        ; Magic
+    DW version    ; FORMAT_VERSION
+    DD features   ; Bits of opcode extensions
+    DD ends...    ; End offset of each section in body
+    DD checksum   ; CRC-32 of body
+    ...           ; Body with sections
+ */
 pub struct Module {
     pub opcodes: Vec<u8>,
+    /// Bits of `features` used by code.
+    pub features: u32,
+    pub relocations: Relocations,
     pub labels: NamedLabels,
     pub links: NamedLinks,
+    pub strings: Strings,
+    pub classes: Classes,
+    pub signatures: Signatures,
+    pub calls: Calls,
+    pub functions: Functions,
 }
 
 impl Module {
+    /// Resolves links and checks calls of functions that became known.
     pub fn resolve(&mut self) -> let_result::Result {
-        self.links.resolve(&self.labels, &mut self.opcodes)
+        self.links.resolve(&self.labels, &mut self.opcodes)?;
+        self.calls.check(&self.signatures, &self.strings)
+    }
+
+    /// Reports unresolved links to loaded modules, they are never resolved.
+    pub fn undefined(&self) -> Vec<let_result::Diagnostic> {
+        let modules = self.labels.modules();
+        self.links
+            .check_undefined(&self.strings, |name| modules.contains(module_name(name)))
+    }
+
+    /// Reports every unresolved link, module is complete program.
+    pub fn check_undefined(&self) -> Vec<let_result::Diagnostic> {
+        self.links.check_undefined(&self.strings, |_| true)
     }
 
     pub fn write<W>(&self, mut write: W) -> let_result::Result
     where
         W: std::io::Write,
     {
-        write.write_all(&[b'L', b'E', b'T', 38])?;
-        utils::write_u8_slice(&mut write, &self.opcodes)?;
-        self.labels.write(&mut write)?;
-        self.links.write(&mut write)?;
+        let mut body = Vec::new();
+        let mut ends = Vec::with_capacity(SECTIONS.len());
+        utils::write_u8_slice(&mut body, &self.opcodes)?;
+        ends.push(body.len());
+        self.relocations.write(&mut body)?;
+        ends.push(body.len());
+        self.labels.write(&mut body)?;
+        ends.push(body.len());
+        self.links.write(&mut body)?;
+        ends.push(body.len());
+        self.strings.write(&mut body)?;
+        ends.push(body.len());
+        self.classes.write(&mut body)?;
+        ends.push(body.len());
+        self.signatures.write(&mut body)?;
+        ends.push(body.len());
+        self.calls.write(&mut body)?;
+        ends.push(body.len());
+        self.functions.write(&mut body)?;
+        ends.push(body.len());
+        if body.len() > u32::MAX as usize {
+            return let_result::raise!(let_result::codes::PROGRAM_TOO_BIG, "Program to big");
+        }
+
+        write.write_all(b"LET&")?;
+        write.write_all(&FORMAT_VERSION.to_be_bytes())?;
+        utils::write_u32(&mut write, self.features)?;
+        for end in ends {
+            utils::write_u32(&mut write, end as u32)?;
+        }
+        utils::write_u32(&mut write, utils::crc32(&body))?;
+        write.write_all(&body)?;
         Ok(())
     }
 
@@ -251,79 +1201,222 @@ impl Module {
     {
         let mut magic = [0u8; 4];
         read.read_exact(&mut magic)?;
-        if magic != [b'L', b'E', b'T', 38] {
-            return let_result::raise!("Unknown format.");
+        if &magic != b"LET&" {
+            return let_result::raise!(let_result::codes::UNKNOWN_FORMAT, "Unknown format.");
+        }
+        let mut version = [0u8; 2];
+        read.read_exact(&mut version)?;
+        let version = u16::from_be_bytes(version);
+        if version != FORMAT_VERSION {
+            return let_result::raise!(
+                let_result::codes::UNSUPPORTED_VERSION,
+                "Module format version {version} is not supported, expected version {FORMAT_VERSION}."
+            );
+        }
+
+        let mut header = [0; SECTIONS.len() + 2];
+        for value in header.iter_mut() {
+            *value = match utils::read_u32(&mut read) {
+                Ok(value) => value,
+                Err(_) => {
+                    return let_result::raise!(
+                        let_result::codes::CORRUPTED_MODULE,
+                        "Module header is truncated."
+                    )
+                }
+            };
+        }
+        let features = header[0];
+        let ends = &header[1..=SECTIONS.len()];
+        let checksum = header[SECTIONS.len() + 1];
+        if ends.windows(2).any(|pair| pair[0] > pair[1]) {
+            return let_result::raise!(
+                let_result::codes::CORRUPTED_MODULE,
+                "Section table of module is corrupted."
+            );
+        }
+        let length = ends[SECTIONS.len() - 1] as usize;
+        let mut body = Vec::new();
+        (&mut read).take(length as u64).read_to_end(&mut body)?;
+        if body.len() != length {
+            return let_result::raise!(
+                let_result::codes::CORRUPTED_MODULE,
+                "Module is truncated, expected {length} bytes of body, found {}.",
+                body.len()
+            );
+        }
+        if utils::crc32(&body) != checksum {
+            return let_result::raise!(
+                let_result::codes::CHECKSUM_MISMATCH,
+                "Checksum of module doesn't match, module is corrupted."
+            );
+        }
+        let unsupported = features & !features::SUPPORTED;
+        if unsupported != 0 {
+            return let_result::raise!(
+                let_result::codes::UNSUPPORTED_FEATURE,
+                "Module uses unsupported opcode extensions 0x{unsupported:08X}."
+            );
         }
-        let opcodes = utils::read_u8_vec(&mut read)?;
-        let labels = NamedLabels::read(&mut read)?;
-        let links = NamedLinks::read(&mut read)?;
+
+        let mut start = 0;
+        let mut sections = ends.iter().zip(SECTIONS).map(|(&end, name)| {
+            let data = &body[start..end as usize];
+            start = end as usize;
+            (data, name)
+        });
+        let mut next = || sections.next().unwrap();
+        let (data, name) = next();
+        let opcodes = section(data, name, utils::read_u8_vec)?;
+        let (data, name) = next();
+        let relocations = section(data, name, Relocations::read)?;
+        let (data, name) = next();
+        let labels = section(data, name, NamedLabels::read)?;
+        let (data, name) = next();
+        let links = section(data, name, NamedLinks::read)?;
+        let (data, name) = next();
+        let strings = section(data, name, Strings::read)?;
+        let (data, name) = next();
+        let classes = section(data, name, Classes::read)?;
+        let (data, name) = next();
+        let signatures = section(data, name, Signatures::read)?;
+        let (data, name) = next();
+        let calls = section(data, name, Calls::read)?;
+        let (data, name) = next();
+        let functions = section(data, name, Functions::read)?;
 
         Ok(Self {
             opcodes,
+            features,
+            relocations,
             labels,
             links,
+            strings,
+            classes,
+            signatures,
+            calls,
+            functions,
         })
     }
 
     pub fn merge(&mut self, other: Self) -> let_result::Result {
         if self.opcodes.len() + other.opcodes.len() > u32::MAX as usize {
-            return let_result::raise!("Program to big");
+            return let_result::raise!(let_result::codes::PROGRAM_TOO_BIG, "Program to big");
         }
 
         let offset = self.opcodes.len() as u32;
-        self.opcodes.reserve(other.opcodes.len());
-        let mut i = 0;
-        while i < other.opcodes.len() {
-            let opcode = other.opcodes[i];
-            i += 1;
-            self.opcodes.push(opcode);
-            match opcode {
-                0x00..=0x2F => (),
-                0x30..=0x4F => {
-                    let b = other.opcodes.get(i).unwrap().clone();
-                    i += 1;
-                    self.opcodes.push(b);
-                }
-                0x50..=0x6F => {
-                    let bytes = &other.opcodes[i..(i + 2)];
-                    i += 2;
-                    if bytes.len() != 2 {
-                        panic!()
-                    }
-                    self.opcodes.extend(bytes);
-                }
-                0x70..=0x8F => {
-                    let mut bytes = [0; 4];
-                    for j in 0..4 {
-                        bytes[j] = other.opcodes.get(i + j).unwrap().clone();
-                    }
-                    i += 4;
-                    if opcode == let_opcodes::JPF
-                        || opcode == let_opcodes::JP
-                        || opcode == let_opcodes::PTR
-                    {
-                        self.opcodes
-                            .extend(&(u32::from_be_bytes(bytes) + offset).to_be_bytes());
-                    } else {
-                        self.opcodes.extend(bytes);
-                    }
-                }
-                0x90..=0xFF => {
-                    let mut bytes = [0; 8];
-                    for j in 0..8 {
-                        bytes[j] = other.opcodes.get(i + j).unwrap().clone();
-                    }
-                    i += 8;
-                    self.opcodes.extend(bytes);
-                }
-            };
-        }
+        let strings_offset = self.strings.len() as u32;
+        let mut opcodes = other.opcodes;
+        other
+            .relocations
+            .relocate(&mut opcodes, offset, strings_offset)?;
+        self.opcodes.extend(opcodes);
+        self.relocations.merge(other.relocations, offset);
+        self.features |= other.features;
 
         self.labels.merge(other.labels, offset)?;
-        self.links.merge(other.links, offset);
+        self.links.merge(other.links, offset, strings_offset);
+        self.strings.merge(other.strings)?;
+        self.classes.merge(other.classes)?;
+        self.signatures.merge(other.signatures);
+        self.calls.merge(other.calls, strings_offset);
+        self.functions
+            .merge(other.functions, offset, strings_offset);
 
         self.resolve()?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Offset of body, it follows magic, version, features, section
+    /// ends and checksum.
+    const BODY: usize = 4 + 2 + 4 + SECTIONS.len() * 4 + 4;
+
+    fn bytes() -> Vec<u8> {
+        let mut strings = Strings::new();
+        strings.push(b"main.let").unwrap();
+        let module = Module {
+            opcodes: vec![let_opcodes::VOID, let_opcodes::RET],
+            features: features::BIG_INTEGERS,
+            relocations: Relocations::new(),
+            labels: NamedLabels::new(),
+            links: NamedLinks::new(),
+            strings,
+            classes: Classes::new(),
+            signatures: Signatures::new(),
+            calls: Calls::new(),
+            functions: Functions::new(),
+        };
+        let mut bytes = Vec::new();
+        module.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Reads module which must be rejected, returns code and message.
+    fn reject(bytes: &[u8]) -> (&'static str, String) {
+        match Module::read(bytes) {
+            Ok(_) => panic!("Module is read."),
+            Err(error) => {
+                let diagnostic = error.into_diagnostic();
+                (diagnostic.code, diagnostic.message)
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let module = Module::read(bytes().as_slice()).unwrap();
+        assert_eq!(module.opcodes, [let_opcodes::VOID, let_opcodes::RET]);
+        assert_eq!(module.features, features::BIG_INTEGERS);
+        assert_eq!(module.strings.get(0), Some(&b"main.let"[..]));
+    }
+
+    #[test]
+    fn wrong_version() {
+        let mut bytes = bytes();
+        bytes[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_be_bytes());
+        assert_eq!(reject(&bytes).0, let_result::codes::UNSUPPORTED_VERSION);
+    }
+
+    #[test]
+    fn unsupported_feature() {
+        let mut bytes = bytes();
+        bytes[6..10].copy_from_slice(&(features::SUPPORTED + 1).to_be_bytes());
+        assert_eq!(reject(&bytes).0, let_result::codes::UNSUPPORTED_FEATURE);
+    }
+
+    #[test]
+    fn truncated_body() {
+        let mut bytes = bytes();
+        bytes.pop();
+        let (code, message) = reject(&bytes);
+        assert_eq!(code, let_result::codes::CORRUPTED_MODULE);
+        assert!(message.starts_with("Module is truncated"), "{message}");
+        assert_eq!(
+            reject(&bytes[..BODY - 1]).0,
+            let_result::codes::CORRUPTED_MODULE
+        );
+    }
+
+    #[test]
+    fn non_monotonic_sections() {
+        let mut bytes = bytes();
+        // End of opcodes section is after end of the next section.
+        bytes[10..14].copy_from_slice(&u32::MAX.to_be_bytes());
+        let (code, message) = reject(&bytes);
+        assert_eq!(code, let_result::codes::CORRUPTED_MODULE);
+        assert!(message.starts_with("Section table"), "{message}");
+    }
+
+    #[test]
+    fn flipped_body_byte() {
+        let mut bytes = bytes();
+        bytes[BODY + 4] ^= 1;
+        assert_eq!(reject(&bytes).0, let_result::codes::CHECKSUM_MISMATCH);
+    }
+}
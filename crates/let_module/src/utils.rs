@@ -11,7 +11,7 @@ pub fn write_u32<W: std::io::Write>(write: &mut W, data: u32) -> let_result::Res
 pub fn write_label<W: std::io::Write>(write: &mut W, data: &[u8]) -> let_result::Result {
     debug_assert!(data.len() <= u8::MAX as usize);
     write_u8(write, data.len() as u8)?;
-    write.write_all(&data)?;
+    write.write_all(data)?;
     Ok(())
 }
 
@@ -24,15 +24,6 @@ pub fn write_u8_slice<W: std::io::Write>(write: &mut W, data: &[u8]) -> let_resu
     Ok(())
 }
 
-pub fn write_u32_slice<W: std::io::Write>(write: &mut W, data: &[u32]) -> let_result::Result {
-    debug_assert!(data.len() <= u32::MAX as usize);
-    write_u32(write, data.len() as u32)?;
-    for value in data {
-        write_u32(write, *value)?;
-    }
-    Ok(())
-}
-
 pub fn read_u8<R: std::io::Read>(read: &mut R) -> let_result::Result<u8> {
     let mut buf = [0];
     read.read_exact(&mut buf)?;
@@ -59,11 +50,36 @@ pub fn read_u8_vec<R: std::io::Read>(read: &mut R) -> let_result::Result<Vec<u8>
     Ok(buf)
 }
 
+pub fn write_u32_slice<W: std::io::Write>(write: &mut W, data: &[u32]) -> let_result::Result {
+    debug_assert!(data.len() <= u32::MAX as usize);
+    write_u32(write, data.len() as u32)?;
+    for value in data {
+        write_u32(write, *value)?;
+    }
+    Ok(())
+}
+
 pub fn read_u32_vec<R: std::io::Read>(read: &mut R) -> let_result::Result<Vec<u32>> {
     let len = read_u32(read)?;
-    let mut result = Vec::with_capacity(len as usize);
+    let mut result = Vec::new();
     for _ in 0..len {
         result.push(read_u32(read)?);
     }
     Ok(result)
 }
+
+/// CRC-32 (IEEE) of `data`.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                crc >> 1 ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -1,6 +1,14 @@
 macro_rules! impl_opcodes {
     ($($name:ident:$value:literal)*) => {
         $(pub const $name: u8 = $value;)*
+
+        /// Name of opcode for disassembly.
+        pub fn name(opcode: u8) -> Option<&'static str> {
+            match opcode {
+                $($value => Some(stringify!($name)),)*
+                _ => None,
+            }
+        }
     };
 }
 
@@ -12,6 +20,11 @@ impl_opcodes!(
     LIST: 0x03
     GET: 0x04 // For indexing
     SET: 0x05 // For indexing
+    SWAP: 0x06
+    DUP: 0x07
+    ROT: 0x08 // Move top value under two values below it
+    NEG: 0x09
+    NOT: 0x0A
 
     // Operators opcodes is in range 0x10..=0x1F
     LS: 0x10
@@ -21,17 +34,30 @@ impl_opcodes!(
     LE: 0x14
     SUB: 0x15
     MUL: 0x16
+    RANGE: 0x17
+    IRANGE: 0x18
+    GE: 0x19
+    NE: 0x1A
+    DIV: 0x1B
+    REM: 0x1C
+    SHL: 0x1D
+    SHR: 0x1E
 
     // 0x30..=0x4F 2 bytes opcodes
     LD1: 0x30
     INT1: 0x31
     CALL: 0x32
     ST1: 0x33
+    CONCAT: 0x34
+    FORMAT: 0x35
+    CLOS: 0x36
 
     // 0x50..=0x6F 3 bytes opcodes
     INT2: 0x50
     LD2: 0x51
     ST2: 0x52
+    LDU: 0x53 // Load variable of enclosing function
+    STU: 0x54 // Store variable of enclosing function
 
     // 0x70..=0x8F 5 byte opcodes
     JPF: 0x70
@@ -40,8 +66,29 @@ impl_opcodes!(
     // STACK: 0x73
     LD4: 0x74
     ST4: 0x75
+    STR: 0x76
+    NEW: 0x77 // Create instance of class, operand is index of class name
+    GETF: 0x78 // Get field, operand is index of field name
+    SETF: 0x79 // Set field, operand is index of field name
+    JPV: 0x7A // Jump if value is void, value is kept
+    JPNV: 0x7B // Jump if value is not void, value is kept, otherwise it is dropped
+    JPFK: 0x7C // Jump if value is false, value is kept, otherwise it is dropped
+    BIGINT: 0x7D // Integer beyond i64, operand is index of string with its big-endian bytes
+    TYPE: 0x7E // Check type of value, operand is index of type name, value is kept
 
     // 0x90..=0xFF 9 byte opcodes
     INT8: 0x90
     REAL: 0x91
+    INVOKE: 0x92 // Call method: index of method name, arguments count, 3 unused bytes
 );
+
+/// Size of operand following opcode, it is given by opcode range.
+pub fn operand_size(opcode: u8) -> usize {
+    match opcode {
+        0x00..=0x2F => 0,
+        0x30..=0x4F => 1,
+        0x50..=0x6F => 2,
+        0x70..=0x8F => 4,
+        0x90..=0xFF => 8,
+    }
+}
@@ -1,39 +1,115 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+
 use crate::{
     operators::{is_double_operator, is_single_operator, is_triple_operator},
     token::Token,
+    utf8::Chars,
 };
 
-pub struct Lexer<I: Iterator> {
-    iter: std::iter::Peekable<I>,
+/// Identifier chars are Unicode XID, `_` may start identifier.
+fn is_identifier_start(c: char) -> bool {
+    c == '_' || unicode_ident::is_xid_start(c)
+}
+
+fn is_identifier_continue(c: char) -> bool {
+    unicode_ident::is_xid_continue(c)
+}
+
+/// Checks if ASCII `c` is operator char.
+fn is_operator(c: char) -> bool {
+    c.is_ascii() && is_single_operator(c as u8)
+}
+
+pub struct Lexer<I: Iterator<Item = u8>> {
+    iter: std::iter::Peekable<Chars<I>>,
     offset: usize,
+    /// Text of current token as UTF-8.
     buffer: Vec<u8>,
+    /// Chars read ahead with their lengths in bytes, they are returned
+    /// before the rest of input and are never line breaks.
+    pending: VecDeque<(char, usize)>,
+    interpolations: Vec<usize>,
+    /// Byte ranges of invalid UTF-8 sequences, they are read as U+FFFD.
+    invalid: Vec<Range<usize>>,
+    /// Line and column of the next char.
+    line: usize,
+    column: usize,
 }
 
-impl<I: Iterator> From<I> for Lexer<I> {
+impl<I: Iterator<Item = u8>> From<I> for Lexer<I> {
     fn from(iter: I) -> Self {
         Self {
-            iter: iter.peekable(),
+            iter: Chars::from(iter).peekable(),
             offset: 0,
             buffer: Vec::new(),
+            pending: VecDeque::new(),
+            interpolations: Vec::new(),
+            invalid: Vec::new(),
+            line: 1,
+            column: 1,
         }
     }
 }
 
 impl<I: Iterator<Item = u8>> Lexer<I> {
-    fn current(&mut self) -> Option<u8> {
-        self.iter.peek().cloned()
+    fn current(&mut self) -> Option<char> {
+        match self.pending.front() {
+            Some(&(c, _)) => Some(c),
+            None => self
+                .iter
+                .peek()
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)),
+        }
     }
 
-    fn next(&mut self) -> Option<u8> {
-        self.iter.next().map(|c| {
-            self.offset += 1;
-            c
-        })
+    fn next(&mut self) -> Option<char> {
+        self.read().map(|(c, _)| c)
+    }
+
+    /// Reads the next char and its length in bytes.
+    fn read(&mut self) -> Option<(char, usize)> {
+        if let Some(pending) = self.pending.pop_front() {
+            return Some(pending);
+        }
+        let (c, length) = match self.iter.next()? {
+            Ok(c) => (c, c.len_utf8()),
+            Err(length) => {
+                self.invalid.push(self.offset..self.offset + length);
+                (char::REPLACEMENT_CHARACTER, length)
+            }
+        };
+        self.offset += length;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some((c, length))
+    }
+
+    fn push(&mut self, c: char) {
+        let mut bytes = [0; 4];
+        self.buffer
+            .extend_from_slice(c.encode_utf8(&mut bytes).as_bytes());
+    }
+
+    /// Skips '.' and checks if it is the first char of range operator.
+    /// In that case the dot is kept as pending char for the next token.
+    fn range_dot(&mut self) -> bool {
+        self.next(); // Skip '.'.
+        if self.current() == Some('.') {
+            self.pending.push_back(('.', 1));
+            true
+        } else {
+            false
+        }
     }
 
     pub fn skip_whitespaces(&mut self) {
         while let Some(c) = self.current() {
-            if c.is_ascii_whitespace() {
+            if c.is_whitespace() {
                 self.next();
             } else {
                 break;
@@ -41,40 +117,58 @@ impl<I: Iterator<Item = u8>> Lexer<I> {
         }
     }
 
-    fn number(&mut self, c: u8) -> Token {
-        let mut has_dot = false;
+    /// Reads number literal, its text is validated by parser. Letters
+    /// following the number are included, so `12ab` is a single token.
+    fn number(&mut self, c: char) -> Token {
+        let mut real = false;
         self.buffer.clear();
-        self.buffer.push(c);
+        self.push(c);
+        let prefixed =
+            c == '0' && matches!(self.current(), Some('x' | 'X' | 'o' | 'O' | 'b' | 'B'));
         while let Some(c) = self.current() {
-            if c.is_ascii_digit() {
-                self.buffer.push(c);
+            if c.is_ascii_digit() || c == '_' {
+                self.push(c);
                 self.next();
-            } else if c == b'.' {
-                if has_dot {
+            } else if c == '.' && !prefixed {
+                if real || self.range_dot() {
                     break;
-                } else {
-                    has_dot = true;
                 }
-                self.buffer.push(c);
+                real = true;
+                self.push(c);
+            } else if matches!(c, 'e' | 'E') && !prefixed {
+                real = true;
+                self.push(c);
+                self.next();
+                if let Some(sign @ ('+' | '-')) = self.current() {
+                    self.push(sign);
+                    self.next();
+                }
+            } else if is_identifier_continue(c) {
+                self.push(c);
                 self.next();
             } else {
                 break;
             }
         }
-        if has_dot {
+        if real {
             Token::Real
         } else {
             Token::Integer
         }
     }
 
-    fn identifier(&mut self, c: u8) -> Token {
+    fn identifier(&mut self, c: char) -> Token {
         self.buffer.clear();
-        self.buffer.push(c);
+        self.push(c);
         while let Some(c) = self.current() {
-            if c.is_ascii_alphanumeric() || c == b'_' || c == b'.' {
-                self.buffer.push(c);
+            if is_identifier_continue(c) {
+                self.push(c);
                 self.next();
+            } else if c == '.' {
+                if self.range_dot() {
+                    break;
+                }
+                self.push(c);
             } else {
                 break;
             }
@@ -82,14 +176,152 @@ impl<I: Iterator<Item = u8>> Lexer<I> {
         Token::Identifier
     }
 
+    fn escape(&mut self) -> Option<char> {
+        Some(match self.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            c => c,
+        })
+    }
+
+    /// Reads string content until closing '"' or interpolation '{'.
+    /// `{{` and `}}` are used to write braces, `{}`, `{:...}` and
+    /// positional `{0...}` are kept as is for `format` placeholders.
+    fn string(&mut self, start: bool) -> Token {
+        self.buffer.clear();
+        while let Some(c) = self.next() {
+            match c {
+                '"' => {
+                    return if start {
+                        Token::String
+                    } else {
+                        Token::InterpolationEnd
+                    };
+                }
+                '\\' => match self.escape() {
+                    Some(c) => self.push(c),
+                    None => break,
+                },
+                '{' if self.current() == Some('{') => {
+                    self.next();
+                    self.push(c);
+                }
+                '{' if matches!(self.current(), Some('}') | Some(':')) => {
+                    self.push(c);
+                }
+                '{' if self.placeholder() => {}
+                '}' if self.current() == Some('}') => {
+                    self.next();
+                    self.push(c);
+                }
+                '{' => {
+                    self.interpolations.push(0);
+                    return if start {
+                        Token::InterpolationStart
+                    } else {
+                        Token::InterpolationMiddle
+                    };
+                }
+                _ => self.push(c),
+            }
+        }
+        self.buffer.clear();
+        self.buffer.push(b'"');
+        Token::Unknown
+    }
+
+    /// Reads positional placeholder like `{0}` or `{1:>4}` after '{'.
+    /// Text that is not a whole placeholder, like `{1 + x}`, is kept
+    /// pending to be lexed as interpolation.
+    fn placeholder(&mut self) -> bool {
+        if !self.current().is_some_and(|c| c.is_ascii_digit()) {
+            return false;
+        }
+        let mut read = Vec::new();
+        let mut spec = false;
+        while let Some(c) = self.current() {
+            let valid = match c {
+                '"' | '\n' | '{' | '\\' => false,
+                '}' | ':' => true,
+                _ => spec || c.is_ascii_digit(),
+            };
+            if !valid {
+                break;
+            }
+            read.extend(self.read());
+            if c == '}' {
+                self.push('{');
+                for (c, _) in read {
+                    self.push(c);
+                }
+                return true;
+            }
+            spec |= c == ':';
+        }
+        read.extend(self.pending.drain(..));
+        self.pending = read.into();
+        false
+    }
+
+    /// Reads character literal like `'a'` or `'\n'`.
+    fn character(&mut self) -> Token {
+        self.buffer.clear();
+        let c = match self.next() {
+            Some('\\') => self.escape(),
+            Some('\'' | '\n') | None => return self.invalid_character(),
+            c => c,
+        };
+        match c {
+            Some(c) if self.current() == Some('\'') => {
+                self.next();
+                self.push(c);
+                Token::Char
+            }
+            None => self.invalid_character(),
+            Some(_) => {
+                // Skip rest of literal with several chars like `'ab'`.
+                while let Some(c) = self.current() {
+                    if c == '\n' {
+                        break;
+                    }
+                    self.next();
+                    if c == '\'' {
+                        break;
+                    }
+                }
+                self.invalid_character()
+            }
+        }
+    }
+
+    fn invalid_character(&mut self) -> Token {
+        self.buffer.clear();
+        self.buffer.push(b'\'');
+        Token::Unknown
+    }
+
+    /// Reads operator starting with ASCII char `c0`.
     fn operator(&mut self, c0: u8) -> Token {
+        if let Some(depth) = self.interpolations.last_mut() {
+            if c0 == b'{' {
+                *depth += 1;
+            } else if c0 == b'}' {
+                if *depth == 0 {
+                    self.interpolations.pop();
+                    return self.string(false);
+                }
+                *depth -= 1;
+            }
+        }
         self.buffer.clear();
         self.buffer.push(c0);
-        if let Some(c1) = self.current() {
+        if let Some(c1) = self.current().filter(char::is_ascii).map(|c| c as u8) {
             if is_double_operator(c0, c1) {
                 self.buffer.push(c1);
                 self.next();
-                if let Some(c2) = self.current() {
+                if let Some(c2) = self.current().filter(char::is_ascii).map(|c| c as u8) {
                     if is_triple_operator(c0, c1, c2) {
                         self.next();
                         self.buffer.push(c2);
@@ -105,22 +337,103 @@ impl<I: Iterator<Item = u8>> Lexer<I> {
 
         Some(if c.is_ascii_digit() {
             self.number(c)
-        } else if c.is_ascii_alphanumeric() || c == b'_' {
+        } else if is_identifier_start(c) {
             self.identifier(c)
-        } else if is_single_operator(c) {
-            self.operator(c)
+        } else if c == '"' {
+            self.string(true)
+        } else if c == '\'' {
+            self.character()
+        } else if is_operator(c) {
+            self.operator(c as u8)
         } else {
             self.buffer.clear();
-            self.buffer.push(c);
+            self.push(c);
             Token::Unknown
         })
     }
 
     pub fn offset(&self) -> usize {
-        self.offset
+        self.offset - self.pending.iter().map(|(_, length)| length).sum::<usize>()
+    }
+
+    /// Position of the next char, pending chars are never line breaks.
+    pub fn position(&self) -> let_result::Position {
+        let_result::Position {
+            line: self.line,
+            column: self.column - self.pending.len(),
+        }
+    }
+
+    /// Takes ranges of invalid UTF-8 sequences read so far.
+    pub fn take_invalid(&mut self) -> Vec<Range<usize>> {
+        std::mem::take(&mut self.invalid)
     }
 
     pub fn buffer(&self) -> &[u8] {
         &self.buffer
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Lexer;
+    use crate::token::Token;
+
+    /// Lexes `source` into tokens with their text.
+    fn lex(source: &str) -> Vec<(Token, String)> {
+        let mut lexer = Lexer::from(source.bytes());
+        let mut tokens = Vec::new();
+        lexer.skip_whitespaces();
+        while let Some(token) = lexer.lex() {
+            tokens.push((token, String::from_utf8_lossy(lexer.buffer()).into_owned()));
+            lexer.skip_whitespaces();
+        }
+        tokens
+    }
+
+    #[test]
+    fn positional_placeholders_are_literal() {
+        assert_eq!(
+            lex(r#""{0} {1} {0}""#),
+            vec![(Token::String, "{0} {1} {0}".to_string())]
+        );
+    }
+
+    #[test]
+    fn expression_starting_with_digit_is_interpolation() {
+        assert_eq!(
+            lex(r#""{0} v = {1 + x}""#),
+            vec![
+                (Token::InterpolationStart, "{0} v = ".to_string()),
+                (Token::Integer, "1".to_string()),
+                (Token::Operator, "+".to_string()),
+                (Token::Identifier, "x".to_string()),
+                (Token::InterpolationEnd, "".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn placeholders_with_spec_are_literal() {
+        assert_eq!(
+            lex(r#""{0:>4} {:.2} {} {12:*^8.1}""#),
+            vec![(Token::String, "{0:>4} {:.2} {} {12:*^8.1}".to_string())]
+        );
+    }
+
+    #[test]
+    fn interpolation_starts_at_other_brace() {
+        let tokens: Vec<Token> = lex(r#""a{x}b{0}""#)
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::InterpolationStart,
+                Token::Identifier,
+                Token::InterpolationEnd
+            ]
+        );
+    }
+}
@@ -1,139 +1,142 @@
 mod lexer;
+mod literal;
 mod operators;
 mod precedence;
 mod token;
+mod utf8;
 
-use std::{collections::HashMap, ops::Range};
+use std::ops::Range;
 
+use let_ast::{
+    Branch, Class, Expression, ExpressionKind, Function, Item, Module, Name, Operator, Parameter,
+    Span,
+};
 pub use let_result::Result;
+use let_result::{codes, Diagnostic};
 
-struct Block {
-    locals: HashMap<Box<[u8]>, u32>,
-}
-
-impl Block {
-    fn new() -> Self {
-        Self {
-            locals: HashMap::new(),
-        }
-    }
-
-    fn var(&mut self, name: &[u8], id: u32) -> bool {
-        if let Some(local) = self.locals.get_mut(name) {
-            *local = id;
-            true
-        } else {
-            self.locals.insert(Vec::from(name).into_boxed_slice(), id);
-            false
-        }
-    }
-
-    fn get(&self, name: &[u8]) -> Option<u32> {
-        self.locals.get(name).cloned()
-    }
-
-    fn len(&self) -> usize {
-        self.locals.len()
+/// Operators of comparison chain must be of the same kind,
+/// `!=` can't be chained.
+fn comparison_kind(operator: &[u8; 3]) -> Option<u8> {
+    match operator {
+        b"<  " | b"<= " => Some(0),
+        b">  " | b">= " => Some(1),
+        b"== " => Some(2),
+        _ => None,
     }
 }
 
-struct Function {
-    blocks: Vec<Block>,
-    local_counter: u32,
-    stack_size: u32,
-}
-
-impl Function {
-    fn new() -> Self {
-        Self {
-            blocks: vec![Block::new()],
-            local_counter: 0,
-            stack_size: 0,
-        }
-    }
-
-    fn push(&mut self) {
-        self.blocks.push(Block::new());
-    }
-
-    fn pop(&mut self) {
-        let block = self.blocks.pop().unwrap();
-        self.local_counter -= block.len() as u32;
-    }
-
-    fn var(&mut self, name: &[u8]) -> u32 {
-        debug_assert!(!self.blocks.is_empty());
-        let len = self.blocks.len();
-        let id = self.local_counter;
-        if !self.blocks[len - 1].var(name, id) {
-            self.local_counter += 1;
-            if self.local_counter > self.stack_size {
-                self.stack_size = self.local_counter;
-            }
-        }
-        id
-    }
-
-    fn get(&self, name: &[u8]) -> Option<u32> {
-        for block in self.blocks.iter().rev() {
-            if let Some(id) = block.get(name) {
-                return Some(id);
-            }
-        }
-        None
-    }
-}
-
-pub struct Parser<'a, I: Iterator> {
+/// Builds syntax tree of source file, code is generated from the tree
+/// by `let_codegen`.
+///
+/// Parser doesn't stop at the first error. Broken statement is skipped
+/// until the next line or block end, broken function or class is skipped
+/// until the next `fn` or `class` at the start of a line.
+pub struct Parser<I: Iterator<Item = u8>> {
     lexer: lexer::Lexer<I>,
     token: Option<token::Token>,
     range: Range<usize>,
-    emitter: &'a mut let_emitter::Emitter,
-    lable_id: usize,
-    functions: Vec<Function>,
-    module: &'a [u8],
+    /// End of previous token, spans of parsed nodes end there.
+    end: usize,
+    /// Current token is the first on its line.
+    newline: bool,
+    /// Column of current token, top level items start at column 1.
+    column: usize,
+    errors: Vec<Diagnostic>,
 }
 
-impl<'a, I> Parser<'a, I>
+impl<I> Parser<I>
 where
     I: Iterator<Item = u8>,
 {
-    pub fn new(iter: I, module: &'a [u8], emitter: &'a mut let_emitter::Emitter) -> Self {
+    pub fn new(iter: I) -> Self {
         Self {
             lexer: iter.into(),
             token: None,
             range: 0..0,
-            emitter,
-            lable_id: 0,
-            functions: vec![Function::new()],
-            module,
+            end: 0,
+            newline: false,
+            column: 1,
+            errors: Vec::new(),
         }
     }
 
-    fn get_lable_id(&mut self) -> usize {
-        let result = self.lable_id;
-        self.lable_id += 1;
-        result
-    }
-
     fn next(&mut self) {
+        self.end = self.range.end;
+        let line = self.lexer.position().line;
         self.lexer.skip_whitespaces();
         let start = self.lexer.offset();
+        let position = self.lexer.position();
+        self.newline = position.line > line;
+        self.column = position.column;
         self.token = self.lexer.lex();
         let end = self.lexer.offset();
         self.range = start..end;
+        for range in self.lexer.take_invalid() {
+            self.errors.push(
+                Diagnostic::error(codes::INVALID_UTF8, "Invalid UTF-8 sequence.".to_string())
+                    .with_range(range),
+            );
+        }
+    }
+
+    /// Span from `start` to the end of the last parsed token.
+    fn span(&self, start: usize) -> Span {
+        Span::new(start, self.end)
+    }
+
+    fn node(&self, start: usize, kind: ExpressionKind) -> Expression {
+        Expression::new(kind, self.span(start))
+    }
+
+    /// Current identifier.
+    fn name(&self) -> Name {
+        Name {
+            value: Vec::from(self.lexer.buffer()).into_boxed_slice(),
+            span: self.range.clone().into(),
+        }
+    }
+
+    /// Skips operator `s` or reports that it is expected.
+    fn expect(&mut self, s: &[u8]) -> let_result::Result {
+        if !self.token_is_buf(token::Token::Operator, s) {
+            return let_result::raise!(
+                codes::EXPECTED_TOKEN,
+                "Expected '{}'.",
+                String::from_utf8_lossy(s)
+            );
+        }
+        self.next();
+        Ok(())
+    }
+
+    /// Skips "end" keyword of block or reports that it is expected.
+    fn expect_end(&mut self) -> let_result::Result {
+        if !self.token_is_buf(token::Token::Identifier, b"end") {
+            return let_result::raise!(codes::EXPECTED_TOKEN, "Expected \"end\".");
+        }
+        self.next();
+        Ok(())
     }
 
-    fn precedence(&self) -> u8 {
+    /// Current operator padded by spaces, like `b"+  "`.
+    fn operator(&self) -> Operator {
+        if !self.token_is(token::Token::Operator) {
+            return [b' ', b' ', b' '];
+        }
         let buf = self.lexer.buffer();
         match buf.len() {
-            1 => precedence::get((buf[0], b' ', b' ')),
-            2 => precedence::get((buf[0], buf[1], b' ')),
-            3 => precedence::get((buf[0], buf[1], buf[2])),
-            _ => 0,
+            1 => [buf[0], b' ', b' '],
+            2 => [buf[0], buf[1], b' '],
+            3 => [buf[0], buf[1], buf[2]],
+            _ => [b' ', b' ', b' '],
         }
     }
 
+    fn is_comparison(&self) -> bool {
+        let [c0, c1, c2] = self.operator();
+        precedence::is_comparison((c0, c1, c2))
+    }
+
     fn token_is(&self, token: token::Token) -> bool {
         if let Some(current) = self.token {
             if current == token {
@@ -152,388 +155,795 @@ where
         false
     }
 
-    fn integer(&mut self) -> let_result::Result {
-        self.emitter
-            .integer(std::str::from_utf8(self.lexer.buffer())?.parse()?)?;
+    fn integer(&mut self) -> let_result::Result<Expression> {
+        let value = literal::integer(std::str::from_utf8(self.lexer.buffer())?)?;
+        let start = self.range.start;
         self.next(); // Skip integer token.
-        Ok(())
+        Ok(self.node(start, ExpressionKind::Integer(value)))
     }
 
-    fn real(&mut self) -> let_result::Result {
-        self.emitter
-            .real(std::str::from_utf8(self.lexer.buffer())?.parse()?)?;
+    /// Character literal is integer with its code point.
+    fn character(&mut self) -> let_result::Result<Expression> {
+        let value = std::str::from_utf8(self.lexer.buffer())?
+            .chars()
+            .next()
+            .map_or(0, |c| c as u32);
+        let start = self.range.start;
+        self.next(); // Skip char token.
+        let value = Box::new(value.to_be_bytes());
+        Ok(self.node(start, ExpressionKind::Integer(value)))
+    }
+
+    fn real(&mut self) -> let_result::Result<Expression> {
+        let value = literal::real(std::str::from_utf8(self.lexer.buffer())?)?;
+        let start = self.range.start;
         self.next(); // Skip real token.
-        Ok(())
+        Ok(self.node(start, ExpressionKind::Real(value)))
     }
 
-    fn paren(&mut self) -> let_result::Result {
-        self.next(); // Skip '(' token.
-        self.expression()?;
-        if !self.token_is_buf(token::Token::Operator, &[b')']) {
-            return let_result::raise!("Expected ')'");
-        }
-        self.next();
-        Ok(())
+    fn string(&mut self) -> let_result::Result<Expression> {
+        let value = Vec::from(self.lexer.buffer()).into_boxed_slice();
+        let start = self.range.start;
+        self.next(); // Skip string token.
+        Ok(self.node(start, ExpressionKind::String(value)))
     }
 
-    fn find_variable(&self, name: &[u8]) -> Option<u32> {
-        self.functions.last().unwrap().get(name)
+    fn paren(&mut self) -> let_result::Result<Expression> {
+        let start = self.range.start;
+        self.next(); // Skip '(' token.
+        let expression = self.expression()?;
+        self.expect(b")")?;
+        Ok(self.node(start, ExpressionKind::Paren(Box::new(expression))))
     }
 
-    fn call(&mut self) -> let_result::Result {
+    fn arguments(&mut self) -> let_result::Result<Vec<Expression>> {
         self.next(); // Skip '('.
 
-        let mut arguments = 0;
+        let mut arguments = Vec::new();
         loop {
-            if arguments > u8::MAX as u32 {
-                return let_result::raise!("Reached maximum function argumens number");
-            }
-
-            if self.token_is_buf(token::Token::Operator, &[b')']) {
+            if self.token_is_buf(token::Token::Operator, b")") {
                 break;
             }
+            if arguments.len() == u8::MAX as usize {
+                return let_result::raise!(
+                    codes::TOO_MANY_ARGUMENTS,
+                    "Reached maximum function argumens number"
+                );
+            }
 
-            self.expression()?;
-            arguments += 1;
+            arguments.push(self.expression()?);
         }
 
         self.next(); // Skip ')'.
 
-        self.emitter.call(arguments as u8)
+        Ok(arguments)
     }
 
-    fn assign(&mut self, index: u32) -> let_result::Result {
-        self.next(); // Skip '='.
-        self.expression()?;
-        self.emitter.store(index);
-        Ok(())
+    /// Interpolated string is a list of its string and expression parts.
+    fn interpolation(&mut self) -> let_result::Result<Expression> {
+        let start = self.range.start;
+        let mut parts = Vec::new();
+        loop {
+            if !self.lexer.buffer().is_empty() {
+                let value = Vec::from(self.lexer.buffer()).into_boxed_slice();
+                parts.push(Expression::new(
+                    ExpressionKind::String(value),
+                    self.range.clone().into(),
+                ));
+            }
+
+            if self.token_is(token::Token::InterpolationEnd) {
+                self.next(); // Skip string end.
+                break;
+            }
+            self.next(); // Skip string part.
+
+            parts.push(self.expression()?);
+
+            if !self.token_is(token::Token::InterpolationMiddle)
+                && !self.token_is(token::Token::InterpolationEnd)
+            {
+                return let_result::raise!(codes::EXPECTED_TOKEN, "Expected '}}'.");
+            }
+        }
+
+        Ok(self.node(start, ExpressionKind::Interpolation(parts)))
     }
 
-    fn index(&mut self, index: u32) -> let_result::Result {
+    /// Parses `[key]` applied to `object`.
+    fn subscript(&mut self, object: Expression, optional: bool) -> let_result::Result<Expression> {
+        let start = object.span.start;
         self.next(); // Skip '['.
 
-        self.emitter.load(index);
-        
-        self.expression()?;
-        
-        if !self.token_is_buf(token::Token::Operator, &[b']']) {
-            return let_result::raise!("Expected ']'.");
+        let index = self.expression()?;
+
+        if !self.token_is_buf(token::Token::Operator, b"]") {
+            return let_result::raise!(codes::EXPECTED_TOKEN, "Expected ']'.");
         }
         self.next(); // Skip ']'.
 
-        match (self.token, self.lexer.buffer()) {
-            (Some(token::Token::Operator), b"=") => {
-                self.next(); // Skip '='.
-                self.expression()?;
-                self.emitter.index_set();
-                Ok(())
+        Ok(self.node(
+            start,
+            ExpressionKind::Index {
+                object: Box::new(object),
+                index: Box::new(index),
+                optional,
             },
-            _ => {
-                self.emitter.index_get();
-                Ok(())
-            }
-        }
+        ))
     }
 
-    fn identifier(&mut self) -> let_result::Result {
-        if let Some(index) = self.find_variable(self.lexer.buffer()) {
-            self.next(); // Skip identifier.
-
-            match (self.token, self.lexer.buffer()) {
-                (Some(token::Token::Operator), b"=") => self.assign(index),
-                (Some(token::Token::Operator), b"[") => self.index(index),
-                _ => {
-                    self.emitter.load(index);
-                    Ok(())
-                }
-            }
-        } else {
-            self.emitter.pointer(self.lexer.buffer())?;
-            self.next(); // Skip identifier.
-
-            match (self.token, self.lexer.buffer()) {
-                (Some(token::Token::Operator), b"(") => self.call(),
-                _ => Ok(()),
-            }
+    /// Calls method of parent class, like `super.init(self)`.
+    fn super_call(&mut self, name: Name) -> let_result::Result<Expression> {
+        let method = &name.value[b"super.".len()..];
+        if method.is_empty() || method.contains(&b'.') {
+            return let_result::raise!(
+                codes::EXPECTED_TOKEN,
+                "Expected method name after \"super\"."
+            );
+        }
+        if !self.token_is_buf(token::Token::Operator, b"(") {
+            return let_result::raise!(
+                codes::EXPECTED_TOKEN,
+                "Expected method call after \"super\"."
+            );
+        }
+        let method = Name {
+            value: Vec::from(method).into_boxed_slice(),
+            span: Span::new(name.span.start + b"super.".len(), name.span.end),
+        };
+        let arguments = self.arguments()?;
+        Ok(self.node(name.span.start, ExpressionKind::Super { method, arguments }))
+    }
+
+    /// Parses identifier, its calls and indexing are postfix operators.
+    fn identifier(&mut self) -> let_result::Result<Expression> {
+        let name = self.name();
+        let start = name.span.start;
+        self.next(); // Skip identifier.
+
+        if name.value.starts_with(b"super.") {
+            return self.super_call(name);
         }
+
+        Ok(self.node(start, ExpressionKind::Identifier(name)))
     }
 
-    fn primary(&mut self) -> let_result::Result {
+    fn operand(&mut self) -> let_result::Result<Expression> {
         match (self.token, self.lexer.buffer()) {
             (Some(token::Token::Identifier), b"if") => self.p_if(),
-            (Some(token::Token::Identifier), b"let") => self.p_let(),
+            (Some(token::Token::Identifier), b"let") => self.p_let(false),
+            (Some(token::Token::Identifier), b"const") => self.p_let(true),
             (Some(token::Token::Identifier), b"while") => self.p_while(),
+            (Some(token::Token::Identifier), b"fn") => self.local_function(),
+            (Some(token::Token::Identifier), b"end" | b"else" | b"elif") => {
+                let_result::raise!(
+                    codes::UNEXPECTED_KEYWORD,
+                    "Unexpected \"{}\".",
+                    String::from_utf8_lossy(self.lexer.buffer())
+                )
+            }
             (Some(token::Token::Identifier), _) => self.identifier(),
             (Some(token::Token::Integer), _) => self.integer(),
+            (Some(token::Token::Char), _) => self.character(),
             (Some(token::Token::Real), _) => self.real(),
+            (Some(token::Token::String), _) => self.string(),
+            (Some(token::Token::InterpolationStart), _) => self.interpolation(),
+            (Some(token::Token::Unknown), b"\"") => {
+                let_result::raise!(codes::UNTERMINATED_STRING, "Unterminated string.")
+            }
+            (Some(token::Token::Unknown), b"'") => Err(Diagnostic::error(
+                codes::INVALID_CHARACTER,
+                "Invalid character literal.".to_string(),
+            )
+            .with_help(
+                "Character literal contains exactly one character, like 'a' or '\\n'.".to_string(),
+            )
+            .into()),
             (Some(token::Token::Operator), b"(") => self.paren(),
             (Some(token::Token::Operator), b"[") => self.list(),
-            _ => let_result::raise!("Unknown token."),
+            _ => let_result::raise!(codes::UNKNOWN_TOKEN, "Unknown token."),
         }
     }
 
-    fn binary(&mut self, precedence: u8) -> let_result::Result {
-        loop {
-            let current_precedence = self.precedence();
+    /// Parses operand with prefix operators, like `-x` or `..5`.
+    fn prefix(&mut self) -> let_result::Result<Expression> {
+        let start = self.range.start;
+        let operator = self.operator();
+        let [c0, c1, c2] = operator;
+        let right = match precedence::prefix((c0, c1, c2)) {
+            Some(right) => right,
+            None => return self.operand(),
+        };
+        self.next(); // Skip operator.
+
+        let kind = match &operator {
+            b"-  " | b"!  " => ExpressionKind::Unary {
+                operator,
+                operand: Box::new(self.expression_bp(right)?),
+            },
+            // Range without start.
+            _ => ExpressionKind::Range {
+                start: None,
+                end: self.range_end(&operator, right)?,
+                inclusive: &operator == b"..=",
+            },
+        };
+        Ok(self.node(start, kind))
+    }
+
+    /// Parses postfix operator `.field`, `?.field`, `[key]`, `?[key]`
+    /// or call `(...)` applied to `object`.
+    fn postfix(
+        &mut self,
+        object: Expression,
+        operator: Operator,
+    ) -> let_result::Result<Expression> {
+        let start = object.span.start;
+        match &operator {
+            b"[  " => return self.subscript(object, false),
+            b"(  " => {
+                let arguments = self.arguments()?;
+                return Ok(self.node(
+                    start,
+                    ExpressionKind::Call {
+                        callee: Box::new(object),
+                        arguments,
+                    },
+                ));
+            }
+            _ => {}
+        }
+        let mut optional = &operator != b".  ";
+        self.next(); // Skip operator.
 
-            if current_precedence < precedence {
-                return Ok(());
+        if &operator == b"?  " {
+            if !self.token_is_buf(token::Token::Operator, b"[") {
+                return let_result::raise!(codes::EXPECTED_TOKEN, "Expected '[' or '.' after '?'.");
             }
+            return self.subscript(object, true);
+        }
 
-            let buf = self.lexer.buffer();
-            let operator = match buf.len() {
-                1 => [buf[0], b' ', b' '],
-                2 => [buf[0], buf[1], b' '],
-                3 => [buf[0], buf[1], buf[2]],
-                _ => [b' ', b' ', b' '],
-            };
+        if !self.token_is(token::Token::Identifier) {
+            return let_result::raise!(codes::EXPECTED_TOKEN, "Expected field name.");
+        }
+        let names = self.name();
+        self.next(); // Skip field names.
+
+        // Lexer reads `a.b.c` as one identifier.
+        let mut object = object;
+        let mut offset = names.span.start;
+        for name in names.value.split(|&c| c == b'.') {
+            if name.is_empty() {
+                return let_result::raise!(codes::EXPECTED_TOKEN, "Expected field name.");
+            }
+            let span = Span::new(offset, offset + name.len());
+            offset = span.end + 1;
+            object = Expression::new(
+                ExpressionKind::Field {
+                    object: Box::new(object),
+                    name: Name {
+                        value: Vec::from(name).into_boxed_slice(),
+                        span,
+                    },
+                    optional,
+                },
+                Span::new(start, span.end),
+            );
+            optional = false;
+        }
+        Ok(object)
+    }
+
+    /// Parses infix operator with `left` operand.
+    fn infix(
+        &mut self,
+        left: Expression,
+        operator: Operator,
+        right: u8,
+    ) -> let_result::Result<Expression> {
+        let start = left.span.start;
+        let [c0, c1, c2] = operator;
+        if precedence::is_assignment((c0, c1, c2)) {
+            if &operator != b"=  " {
+                return let_result::raise!(
+                    codes::UNSUPPORTED_OPERATOR,
+                    "Operator \"{}\" is not supported.",
+                    String::from_utf8_lossy(&operator).trim()
+                );
+            }
+            if !matches!(
+                left.kind,
+                ExpressionKind::Identifier(_)
+                    | ExpressionKind::Field { .. }
+                    | ExpressionKind::Index { .. }
+            ) {
+                return let_result::raise!(
+                    codes::INVALID_ASSIGNMENT_TARGET,
+                    "Invalid assignment target."
+                );
+            }
+            self.next(); // Skip '='.
+            let value = self.expression_bp(right)?;
+            return Ok(self.node(
+                start,
+                ExpressionKind::Assign {
+                    target: Box::new(left),
+                    value: Box::new(value),
+                },
+            ));
+        }
+        let is_comparison = self.is_comparison();
+        self.next(); // Skip operator.
+
+        let kind = if is_comparison {
+            self.comparison(left, operator, right)?
+        } else if &operator == b".. " || &operator == b"..=" {
+            ExpressionKind::Range {
+                start: Some(Box::new(left)),
+                end: self.range_end(&operator, right)?,
+                inclusive: &operator == b"..=",
+            }
+        } else {
+            ExpressionKind::Binary {
+                operator,
+                left: Box::new(left),
+                right: Box::new(self.expression_bp(right)?),
+            }
+        };
+        Ok(self.node(start, kind))
+    }
+
+    /// Parses right operand of comparison, continuing the chain
+    /// like `0 <= i < n`.
+    fn comparison(
+        &mut self,
+        first: Expression,
+        operator: Operator,
+        right: u8,
+    ) -> let_result::Result<ExpressionKind> {
+        let second = self.expression_bp(right)?;
+        if !self.is_comparison() {
+            return Ok(ExpressionKind::Binary {
+                operator,
+                left: Box::new(first),
+                right: Box::new(second),
+            });
+        }
 
+        let mut rest = vec![(operator, second)];
+        let mut operator = operator;
+        while self.is_comparison() {
+            let next = self.operator();
+            if comparison_kind(&operator).is_none() || comparison_kind(&next).is_none() {
+                let diagnostic = Diagnostic::error(
+                    codes::COMPARISON_CHAIN,
+                    "Operator \"!=\" can't be chained.".to_string(),
+                );
+                return Err(diagnostic.with_help("Use \"&&\".".to_string()).into());
+            }
+            if comparison_kind(&operator) != comparison_kind(&next) {
+                let diagnostic = Diagnostic::error(
+                    codes::COMPARISON_CHAIN,
+                    format!(
+                        "Comparison chain mixes \"{}\" and \"{}\".",
+                        String::from_utf8_lossy(&operator).trim(),
+                        String::from_utf8_lossy(&next).trim()
+                    ),
+                );
+                return Err(diagnostic
+                    .with_help("Use parentheses or \"&&\".".to_string())
+                    .into());
+            }
             self.next(); // Skip operator.
+            rest.push((next, self.expression_bp(right)?));
+            operator = next;
+        }
+        Ok(ExpressionKind::Comparison {
+            first: Box::new(first),
+            rest,
+        })
+    }
+
+    /// Checks if range has no end, like `list[1..]` or `1..` at the end
+    /// of line.
+    fn is_open_range(&self) -> bool {
+        match (self.token, self.lexer.buffer()) {
+            (None, _) => true,
+            (Some(token::Token::Operator), b"]" | b")" | b"}") => true,
+            (Some(token::Token::Identifier), b"end" | b"else" | b"elif") => true,
+            _ => self.newline,
+        }
+    }
 
-            self.primary()?;
+    /// Parses range end, range may have no end, like `list[1..]`.
+    fn range_end(
+        &mut self,
+        operator: &Operator,
+        right: u8,
+    ) -> let_result::Result<Option<Box<Expression>>> {
+        if self.is_open_range() {
+            if operator == b"..=" {
+                return let_result::raise!(
+                    codes::RANGE_WITHOUT_END,
+                    "Inclusive range must have an end."
+                );
+            }
+            Ok(None)
+        } else {
+            Ok(Some(Box::new(self.expression_bp(right)?)))
+        }
+    }
 
-            self.emitter.binary(operator)?;
+    /// Pratt parser, parses expression while operators bind to
+    /// the left stronger than `min_bp`.
+    fn expression_bp(&mut self, min_bp: u8) -> let_result::Result<Expression> {
+        let mut left = self.prefix()?;
 
-            let next_precedence = self.precedence();
-            if current_precedence < next_precedence {
-                return self.binary(current_precedence + 1);
+        loop {
+            let operator = self.operator();
+            let [c0, c1, c2] = operator;
+            if let Some(power) = precedence::postfix((c0, c1, c2)) {
+                // Bracket after whitespace starts the next list item or
+                // argument, like `[a [1]]` or `f(x (-1))`.
+                let detached = self.range.start > self.end;
+                if power < min_bp || (matches!(&operator, b"[  " | b"(  ") && detached) {
+                    break;
+                }
+                left = self.postfix(left, operator)?;
+                continue;
+            }
+
+            let (power, right) = match precedence::infix((c0, c1, c2)) {
+                Some(powers) => powers,
+                None => break,
+            };
+            if power < min_bp {
+                break;
             }
+            left = self.infix(left, operator, right)?;
         }
+
+        Ok(left)
     }
 
-    fn block(&mut self, ends: &[&[u8]]) -> let_result::Result {
-        let mut first = true;
+    fn block(&mut self, ends: &[&[u8]]) -> let_result::Result<Vec<Expression>> {
+        let mut expressions = Vec::new();
 
         loop {
-            let mut found = false;
+            let mut found = self.token.is_none();
             for &end in ends {
                 if self.token_is_buf(token::Token::Identifier, end) {
                     found = true;
                 }
             }
+            // After an error, block without "end" is closed by the next item.
+            if !self.errors.is_empty() && self.is_item_start() {
+                found = true;
+            }
 
             if found {
                 break;
             }
 
-            if first {
-                first = false;
-            } else {
-                self.emitter.drop()?;
+            let start = self.range.start;
+            match self.expression() {
+                Ok(expression) => expressions.push(expression),
+                Err(error) => {
+                    self.report(error);
+                    self.synchronize(start);
+                }
             }
-
-            self.expression()?;
         }
 
-        Ok(())
+        Ok(expressions)
     }
 
-    fn expression(&mut self) -> let_result::Result {
-        self.primary()?;
-        self.binary(1)
+    /// Collects error located at current token.
+    fn report(&mut self, error: let_result::Error) {
+        let diagnostic = error.locate(self.range()).into_diagnostic();
+        // Error of nested construct is reported once.
+        if self
+            .errors
+            .last()
+            .is_some_and(|last| last.range == diagnostic.range)
+        {
+            return;
+        }
+        self.errors.push(diagnostic);
+    }
+
+    /// Skips tokens of broken statement which starts at `start`
+    /// until the next line or end of block.
+    fn synchronize(&mut self, start: usize) {
+        while self.token.is_some() {
+            if self.token_is_buf(token::Token::Identifier, b"end")
+                || self.token_is_buf(token::Token::Identifier, b"else")
+                || self.token_is_buf(token::Token::Identifier, b"elif")
+                || (self.newline && self.range.start > start)
+            {
+                break;
+            }
+            self.next();
+        }
     }
 
-    fn enter_block(&mut self) {
-        self.functions.last_mut().unwrap().push();
+    /// Skips tokens of broken item which starts at `start`
+    /// until the next item.
+    fn synchronize_item(&mut self, start: usize) {
+        while self.token.is_some() {
+            if self.is_item_start() && self.range.start > start {
+                break;
+            }
+            self.next();
+        }
     }
 
-    fn exit_block(&mut self) {
-        self.functions.last_mut().unwrap().pop();
+    /// Checks if current token is `fn` or `class` at the start of a line.
+    fn is_item_start(&self) -> bool {
+        self.column == 1
+            && (self.token_is_buf(token::Token::Identifier, b"fn")
+                || self.token_is_buf(token::Token::Identifier, b"class"))
     }
 
-    fn add_local(&mut self) -> u32 {
-        self.functions.last_mut().unwrap().var(self.lexer.buffer())
+    fn expression(&mut self) -> let_result::Result<Expression> {
+        self.expression_bp(0)
     }
 
-    fn list(&mut self) -> let_result::Result {
+    fn list(&mut self) -> let_result::Result<Expression> {
+        let start = self.range.start;
         self.next(); // Skip '['.
 
-        self.emitter.list();
-
+        let mut items = Vec::new();
         loop {
             if self.token_is_buf(token::Token::Operator, b"]") {
                 break;
             }
 
-            self.expression()?;
-            self.emitter.binary([b'+', b' ', b' '])?;
+            items.push(self.expression()?);
         }
 
         self.next(); // Skip ']'.
 
-        Ok(())
+        Ok(self.node(start, ExpressionKind::List(items)))
     }
 
-    fn p_let(&mut self) -> let_result::Result {
-        self.next(); // Skip "let"
+    fn p_let(&mut self, constant: bool) -> let_result::Result<Expression> {
+        let start = self.range.start;
+        self.next(); // Skip "let" or "const"
 
         if !self.token_is(token::Token::Identifier) {
-            return let_result::raise!("Expected variable name.");
+            return let_result::raise!(codes::EXPECTED_TOKEN, "Expected variable name.");
         }
 
-        let local_id = self.add_local();
+        let name = self.name();
         self.next(); // Skip variable name.
 
-        if !self.token_is_buf(token::Token::Operator, &[b'=']) {
-            return let_result::raise!("Expected '='.");
-        }
-        self.next(); // Skip '='
+        let annotation = self.annotation(b":")?;
 
-        self.expression()?;
-        self.emitter.store(local_id);
+        self.expect(b"=")?;
 
-        Ok(())
+        let value = self.expression()?;
+
+        Ok(self.node(
+            start,
+            ExpressionKind::Let {
+                name,
+                annotation,
+                constant,
+                value: Box::new(value),
+            },
+        ))
     }
 
-    fn p_if(&mut self) -> let_result::Result {
-        let end_if_id = self.get_lable_id();
+    fn p_if(&mut self) -> let_result::Result<Expression> {
+        let start = self.range.start;
         self.next(); // Skip "if"
 
-        // Condition
-        self.expression()?;
-        let mut next_id = self.get_lable_id();
-        self.emitter.jump_false(next_id as u32)?;
-
-        // Block.
-        self.enter_block();
-        self.block(&[b"end", b"else", b"elif"])?;
-        self.exit_block();
-
+        let mut branches = Vec::new();
+        let mut otherwise = None;
+        let mut branch_start = start;
         loop {
-            if self.token_is_buf(token::Token::Identifier, b"end") {
-                self.emitter.label(next_id as u32)?;
-                self.emitter.label(end_if_id as u32)?;
-                self.next(); // Skip "end"
-                break;
-            } else if self.token_is_buf(token::Token::Identifier, b"elif") {
-                self.emitter.jump(end_if_id as u32)?;
-                self.emitter.label(next_id as u32)?;
+            let condition = self.expression()?;
+            let body = self.block(&[b"end", b"else", b"elif"])?;
+            branches.push(Branch {
+                condition,
+                body,
+                span: self.span(branch_start),
+            });
+
+            if self.token_is_buf(token::Token::Identifier, b"elif") {
+                branch_start = self.range.start;
                 self.next(); // Skip "elif"
-                self.expression()?; // Condition.
-                next_id = self.get_lable_id();
-                self.emitter.jump_false(next_id as u32)?;
-                // Block.
-                self.enter_block();
-                self.block(&[b"end", b"else", b"elif"])?;
-                self.exit_block();
-            } else if self.token_is_buf(token::Token::Identifier, b"else") {
-                self.emitter.jump(end_if_id as u32)?;
-                self.emitter.label(next_id as u32)?;
-                // Block.
-                self.enter_block();
+                continue;
+            }
+            if self.token_is_buf(token::Token::Identifier, b"else") {
                 self.next(); // Skip "else"
-                self.block(&[b"end"])?;
-                self.exit_block();
-                self.emitter.label(end_if_id as u32)?;
-                self.next(); // Skip "end"
-                break;
+                otherwise = Some(self.block(&[b"end"])?);
             }
+            break;
         }
+        self.expect_end()?;
 
-        Ok(())
+        Ok(self.node(
+            start,
+            ExpressionKind::If {
+                branches,
+                otherwise,
+            },
+        ))
     }
 
-    fn p_while(&mut self) -> let_result::Result {
+    fn p_while(&mut self) -> let_result::Result<Expression> {
+        let start = self.range.start;
         self.next(); // Skip "while"
 
-        self.emitter.void();
+        let condition = self.expression()?;
+        let body = self.block(&[b"end"])?;
+        self.expect_end()?;
+
+        Ok(self.node(
+            start,
+            ExpressionKind::While {
+                condition: Box::new(condition),
+                body,
+            },
+        ))
+    }
+
+    /// Parses optional type annotation introduced by `operator`,
+    /// like `: int` or `-> main.Vec`.
+    fn annotation(&mut self, operator: &[u8]) -> let_result::Result<Option<Name>> {
+        if !self.token_is_buf(token::Token::Operator, operator) {
+            return Ok(None);
+        }
+        self.next(); // Skip operator.
 
-        let while_start = self.emitter.offset();
+        if !self.token_is(token::Token::Identifier) {
+            return let_result::raise!(codes::EXPECTED_TOKEN, "Expected type name.");
+        }
+        let name = self.name();
+        self.next(); // Skip type name.
+        Ok(Some(name))
+    }
 
-        // Condition
-        self.expression()?;
-        let end_id = self.get_lable_id();
-        self.emitter.jump_false(end_id as u32)?;
+    /// Parses parameters and body of function, `start` is the offset
+    /// of "fn" keyword.
+    fn function_body(&mut self, name: Name, start: usize) -> let_result::Result<Function> {
+        self.expect(b"(")?;
 
-        self.emitter.drop()?;
+        let mut parameters = Vec::new();
+        while self.token_is(token::Token::Identifier) {
+            if parameters.len() == u8::MAX as usize {
+                return let_result::raise!(
+                    codes::TOO_MANY_ARGUMENTS,
+                    "Reached maximum function argumens number"
+                );
+            }
+            let name = self.name();
+            self.next(); // Skip parameter name.
+            let annotation = self.annotation(b":")?;
+            parameters.push(Parameter { name, annotation });
+        }
 
-        // Block.
-        self.enter_block();
-        self.block(&[b"end"])?;
-        self.exit_block();
+        self.expect(b")")?;
 
-        self.emitter.jump_to(while_start);
+        let returns = self.annotation(b"->")?;
 
-        self.emitter.label(end_id as u32)?;
-        self.next(); // Skip "end"
+        let body = self.block(&[b"end"])?;
+        self.expect_end()?;
 
-        Ok(())
+        Ok(Function {
+            name,
+            parameters,
+            returns,
+            body,
+            span: self.span(start),
+        })
     }
 
-    fn function(&mut self) -> let_result::Result {
+    /// Parses function name and the rest of function,
+    /// names of members and local functions can't be dotted.
+    fn named_function(&mut self, dotted: bool, message: &str) -> let_result::Result<Function> {
+        let start = self.range.start;
         self.next(); // Skip "fn"
 
-        if !self.token_is(token::Token::Identifier) {
-            return let_result::raise!("Expected function name.");
+        if !self.token_is(token::Token::Identifier)
+            || (!dotted && self.lexer.buffer().contains(&b'.'))
+        {
+            return let_result::raise!(codes::EXPECTED_TOKEN, "{message}");
         }
-
-        let mut full_name = Vec::with_capacity(self.module.len() + self.lexer.buffer().len() + 1);
-        full_name.extend(self.module);
-        full_name.push(b'.');
-        full_name.extend(self.lexer.buffer());
-        self.emitter.label_named(full_name.into_boxed_slice())?;
+        let name = self.name();
         self.next(); // Skip function name.
 
-        if !self.token_is_buf(token::Token::Operator, &[b'(']) {
-            return let_result::raise!("Expected '('.");
-        }
-        self.next(); // Skip '('.
+        self.function_body(name, start)
+    }
+
+    fn function(&mut self) -> let_result::Result<Function> {
+        self.named_function(true, "Expected function name.")
+    }
 
-        self.functions.push(Function::new());
+    fn class(&mut self) -> let_result::Result<Class> {
+        let start = self.range.start;
+        self.next(); // Skip "class"
 
-        let mut args_count = 0;
-        while self.token_is(token::Token::Identifier) {
-            if args_count > u8::MAX as u32 {
-                return let_result::raise!("Reached maximum function argumens number");
+        if !self.token_is(token::Token::Identifier) || self.lexer.buffer().contains(&b'.') {
+            return let_result::raise!(codes::EXPECTED_TOKEN, "Expected class name.");
+        }
+        let name = self.name();
+        self.next(); // Skip class name.
+
+        let mut parent = None;
+        if self.token_is_buf(token::Token::Operator, b"(") {
+            self.next(); // Skip '('.
+            if !self.token_is(token::Token::Identifier) {
+                return let_result::raise!(codes::EXPECTED_TOKEN, "Expected parent class name.");
             }
-            self.add_local();
-            self.next();
-            args_count += 1;
+            parent = Some(self.name());
+            self.next(); // Skip parent name.
+            self.expect(b")")?;
         }
 
-        if !self.token_is_buf(token::Token::Operator, &[b')']) {
-            return let_result::raise!("Expected ')'.");
+        let mut methods = Vec::new();
+        loop {
+            if self.token_is_buf(token::Token::Identifier, b"end") {
+                self.next(); // Skip "end".
+                break;
+            }
+            if !self.token_is_buf(token::Token::Identifier, b"fn") {
+                return let_result::raise!(codes::EXPECTED_TOKEN, "Expected method or \"end\".");
+            }
+            methods.push(self.named_function(false, "Expected method name.")?);
         }
-        self.next(); // Skip ')'.
-
-        let stack_size_address = self.emitter.function(args_count as u8)?;
-
-        self.block(&[b"end"])?;
-        self.next(); // Skip "end".
 
-        self.emitter.ret()?;
-
-        let function = self.functions.pop().unwrap();
-
-        (function.stack_size - args_count)
-            .to_be_bytes()
-            .iter()
-            .cloned()
-            .enumerate()
-            .for_each(|(i, b)| self.emitter.set(stack_size_address + i as u32, b));
+        Ok(Class {
+            name,
+            parent,
+            methods,
+            span: self.span(start),
+        })
+    }
 
-        Ok(())
+    /// Function declared inside other function.
+    fn local_function(&mut self) -> let_result::Result<Expression> {
+        let function = self.named_function(false, "Expected function name.")?;
+        let span = function.span;
+        Ok(Expression::new(
+            ExpressionKind::Function(Box::new(function)),
+            span,
+        ))
     }
 
-    fn global_code(&mut self) -> let_result::Result {
-        self.emitter.label_named(Vec::from(self.module).into_boxed_slice())?;
-        self.expression()?;
-        self.emitter.ret()
+    fn item(&mut self) -> let_result::Result<Item> {
+        Ok(match (self.token, self.lexer.buffer()) {
+            (Some(token::Token::Identifier), b"fn") => Item::Function(self.function()?),
+            (Some(token::Token::Identifier), b"class") => Item::Class(self.class()?),
+            _ => Item::Code(self.expression()?),
+        })
     }
 
-    pub fn parse(&mut self) -> let_result::Result {
+    /// Parses the whole source, the tree is complete only if there are
+    /// no `errors`.
+    pub fn parse(&mut self) -> Module {
         self.next();
-        loop {
-            match (self.token, self.lexer.buffer()) {
-                (None, _) => break,
-                (Some(token::Token::Identifier), b"fn") => self.function()?,
-                _ => self.global_code()?,
+        let mut module = Module::default();
+        while self.token.is_some() {
+            let start = self.range.start;
+            match self.item() {
+                Ok(item) => module.items.push(item),
+                Err(error) => {
+                    self.report(error);
+                    self.synchronize_item(start);
+                }
             }
         }
-        Ok(())
+        module
     }
 
-    pub fn range(&self) -> std::ops::Range<usize> {
+    fn range(&self) -> std::ops::Range<usize> {
         self.range.clone()
     }
+
+    pub fn errors(&self) -> &[Diagnostic] {
+        &self.errors
+    }
 }
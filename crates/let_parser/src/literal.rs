@@ -0,0 +1,78 @@
+use let_result::codes;
+
+/// Parses integer literal like `1_000`, `0xFF`, `0o17` or `0b1010`
+/// of any size. Returns big-endian bytes of its value.
+pub fn integer(text: &str) -> let_result::Result<Box<[u8]>> {
+    let (radix, name, digits) = match text.get(..2) {
+        Some("0x" | "0X") => (16, "hexadecimal", &text[2..]),
+        Some("0o" | "0O") => (8, "octal", &text[2..]),
+        Some("0b" | "0B") => (2, "binary", &text[2..]),
+        _ => (10, "decimal", text),
+    };
+    // Little-endian bytes while digits are added.
+    let mut value: Vec<u8> = Vec::new();
+    let mut empty = true;
+    for c in digits.chars().filter(|&c| c != '_') {
+        let Some(digit) = c.to_digit(radix) else {
+            return let_result::raise!(
+                codes::INVALID_NUMBER,
+                "Invalid digit \"{c}\" in {name} literal."
+            );
+        };
+        empty = false;
+        let mut carry = digit;
+        for byte in value.iter_mut() {
+            let product = *byte as u32 * radix + carry;
+            *byte = product as u8;
+            carry = product >> 8;
+        }
+        if carry > 0 {
+            value.push(carry as u8);
+        }
+    }
+    if empty {
+        return let_result::raise!(codes::INVALID_NUMBER, "Missing digits in {name} literal.");
+    }
+    value.reverse();
+    Ok(value.into_boxed_slice())
+}
+
+/// Parses real literal like `1.5`, `1_000.25` or `1e-9`.
+/// Literal beyond `f64` range, like `1e999`, is rejected.
+pub fn real(text: &str) -> let_result::Result<f64> {
+    let digits: String = text.chars().filter(|&c| c != '_').collect();
+    match digits.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        Ok(_) => let_result::raise!(
+            codes::INVALID_NUMBER,
+            "Real literal \"{text}\" is out of range."
+        ),
+        Err(_) => let_result::raise!(codes::INVALID_NUMBER, "Invalid real literal \"{text}\"."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::real;
+    use let_result::codes;
+
+    fn code<T>(result: let_result::Result<T>) -> Option<&'static str> {
+        result.err().map(|error| error.into_diagnostic().code)
+    }
+
+    #[test]
+    fn reals() {
+        assert_eq!(real("1_000.25").unwrap(), 1000.25);
+        assert_eq!(real("1e-9").unwrap(), 1e-9);
+        assert_eq!(real("1.7976931348623157e308").unwrap(), f64::MAX);
+        assert_eq!(code(real("1.5e")), Some(codes::INVALID_NUMBER));
+    }
+
+    #[test]
+    fn non_finite_real_is_rejected() {
+        assert_eq!(code(real("1e999")), Some(codes::INVALID_NUMBER));
+        assert_eq!(code(real("1_0.0e3_08")), Some(codes::INVALID_NUMBER));
+        // Underflow is rounded to zero like in other languages.
+        assert_eq!(real("1e-999").unwrap(), 0.0);
+    }
+}
@@ -1,49 +1,117 @@
 const ASSIGNMENT_PRECEDENCE: u8 = 2;
-const OR_PRECEDENCE: u8 = 3;
-const AND_PRECEDENCE: u8 = 4;
-const COMPARISON_PRECEDENCE: u8 = 5;
-const BITWISE_XOR_PRECEDENCE: u8 = 6;
-const BITWISE_OR_PRECEDENCE: u8 = 7;
-const BITWISE_AND_PRECEDENCE: u8 = 8;
-const SHIFTS_PRECEDENCE: u8 = 9;
-const TERM_PRECEDENCE: u8 = 10;
-const FACTOR_PRECEDENCE: u8 = 11;
-
-const PRECEDENCES: [(u8, u8, u8, u8); 29] = [
-    (b'!', b'=', b' ', COMPARISON_PRECEDENCE),
-    (b'%', b' ', b' ', FACTOR_PRECEDENCE),
-    (b'%', b'=', b' ', ASSIGNMENT_PRECEDENCE),
-    (b'&', b' ', b' ', BITWISE_AND_PRECEDENCE),
-    (b'&', b'&', b' ', AND_PRECEDENCE),
-    (b'&', b'=', b' ', ASSIGNMENT_PRECEDENCE),
-    (b'*', b' ', b' ', FACTOR_PRECEDENCE),
-    (b'*', b'=', b' ', ASSIGNMENT_PRECEDENCE),
-    (b'+', b' ', b' ', TERM_PRECEDENCE),
-    (b'+', b'=', b' ', ASSIGNMENT_PRECEDENCE),
-    (b'-', b' ', b' ', TERM_PRECEDENCE),
-    (b'-', b'=', b' ', ASSIGNMENT_PRECEDENCE),
-    (b'/', b' ', b' ', FACTOR_PRECEDENCE),
-    (b'/', b'=', b' ', ASSIGNMENT_PRECEDENCE),
-    (b'<', b' ', b' ', COMPARISON_PRECEDENCE),
-    (b'<', b'<', b' ', SHIFTS_PRECEDENCE),
-    (b'<', b'<', b'=', ASSIGNMENT_PRECEDENCE),
-    (b'<', b'=', b' ', COMPARISON_PRECEDENCE),
-    (b'=', b' ', b' ', ASSIGNMENT_PRECEDENCE),
-    (b'=', b'=', b' ', COMPARISON_PRECEDENCE),
-    (b'>', b' ', b' ', COMPARISON_PRECEDENCE),
-    (b'>', b'=', b' ', COMPARISON_PRECEDENCE),
-    (b'>', b'>', b' ', SHIFTS_PRECEDENCE),
-    (b'>', b'>', b'=', ASSIGNMENT_PRECEDENCE),
-    (b'^', b' ', b' ', BITWISE_XOR_PRECEDENCE),
-    (b'^', b'=', b' ', ASSIGNMENT_PRECEDENCE),
-    (b'|', b' ', b' ', BITWISE_OR_PRECEDENCE),
-    (b'|', b'=', b' ', ASSIGNMENT_PRECEDENCE),
-    (b'|', b'|', b' ', OR_PRECEDENCE),
+const COALESCE_PRECEDENCE: u8 = 3;
+const OR_PRECEDENCE: u8 = 4;
+const AND_PRECEDENCE: u8 = 5;
+const COMPARISON_PRECEDENCE: u8 = 6;
+const RANGE_PRECEDENCE: u8 = 7;
+const BITWISE_XOR_PRECEDENCE: u8 = 8;
+const BITWISE_OR_PRECEDENCE: u8 = 9;
+const BITWISE_AND_PRECEDENCE: u8 = 10;
+const SHIFTS_PRECEDENCE: u8 = 11;
+const TERM_PRECEDENCE: u8 = 12;
+const FACTOR_PRECEDENCE: u8 = 13;
+/// Prefix operators bind tighter than any infix operator.
+const PREFIX_PRECEDENCE: u8 = 14;
+/// Postfix operators like `.field`, `[key]` or call bind tighter than
+/// prefix ones.
+const POSTFIX_PRECEDENCE: u8 = 15;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+use Associativity::{Left, Right};
+
+const PRECEDENCES: [(u8, u8, u8, u8, Associativity); 32] = [
+    (b'!', b'=', b' ', COMPARISON_PRECEDENCE, Left),
+    (b'%', b' ', b' ', FACTOR_PRECEDENCE, Left),
+    (b'%', b'=', b' ', ASSIGNMENT_PRECEDENCE, Right),
+    (b'&', b' ', b' ', BITWISE_AND_PRECEDENCE, Left),
+    (b'&', b'&', b' ', AND_PRECEDENCE, Left),
+    (b'&', b'=', b' ', ASSIGNMENT_PRECEDENCE, Right),
+    (b'*', b' ', b' ', FACTOR_PRECEDENCE, Left),
+    (b'*', b'=', b' ', ASSIGNMENT_PRECEDENCE, Right),
+    (b'+', b' ', b' ', TERM_PRECEDENCE, Left),
+    (b'+', b'=', b' ', ASSIGNMENT_PRECEDENCE, Right),
+    (b'-', b' ', b' ', TERM_PRECEDENCE, Left),
+    (b'-', b'=', b' ', ASSIGNMENT_PRECEDENCE, Right),
+    (b'.', b'.', b' ', RANGE_PRECEDENCE, Left),
+    (b'.', b'.', b'=', RANGE_PRECEDENCE, Left),
+    (b'/', b' ', b' ', FACTOR_PRECEDENCE, Left),
+    (b'/', b'=', b' ', ASSIGNMENT_PRECEDENCE, Right),
+    (b'<', b' ', b' ', COMPARISON_PRECEDENCE, Left),
+    (b'<', b'<', b' ', SHIFTS_PRECEDENCE, Left),
+    (b'<', b'<', b'=', ASSIGNMENT_PRECEDENCE, Right),
+    (b'<', b'=', b' ', COMPARISON_PRECEDENCE, Left),
+    (b'=', b' ', b' ', ASSIGNMENT_PRECEDENCE, Right),
+    (b'=', b'=', b' ', COMPARISON_PRECEDENCE, Left),
+    (b'>', b' ', b' ', COMPARISON_PRECEDENCE, Left),
+    (b'>', b'=', b' ', COMPARISON_PRECEDENCE, Left),
+    (b'>', b'>', b' ', SHIFTS_PRECEDENCE, Left),
+    (b'>', b'>', b'=', ASSIGNMENT_PRECEDENCE, Right),
+    (b'?', b'?', b' ', COALESCE_PRECEDENCE, Right),
+    (b'^', b' ', b' ', BITWISE_XOR_PRECEDENCE, Left),
+    (b'^', b'=', b' ', ASSIGNMENT_PRECEDENCE, Right),
+    (b'|', b' ', b' ', BITWISE_OR_PRECEDENCE, Left),
+    (b'|', b'=', b' ', ASSIGNMENT_PRECEDENCE, Right),
+    (b'|', b'|', b' ', OR_PRECEDENCE, Left),
 ];
 
+fn find(c: (u8, u8, u8)) -> Option<(u8, Associativity)> {
+    PRECEDENCES
+        .binary_search_by_key(&c, |&c| (c.0, c.1, c.2))
+        .ok()
+        .map(|index| (PRECEDENCES[index].3, PRECEDENCES[index].4))
+}
+
 pub fn get(c: (u8, u8, u8)) -> u8 {
-    match PRECEDENCES.binary_search_by_key(&c, |&c| (c.0, c.1, c.2)) {
-        Ok(index) => PRECEDENCES[index].3,
-        Err(_) => 0,
+    find(c).map_or(0, |(precedence, _)| precedence)
+}
+
+pub fn is_comparison(c: (u8, u8, u8)) -> bool {
+    get(c) == COMPARISON_PRECEDENCE
+}
+
+pub fn is_assignment(c: (u8, u8, u8)) -> bool {
+    get(c) == ASSIGNMENT_PRECEDENCE
+}
+
+/*
+   Binding powers of Pratt parser. Infix operator has left and right
+   powers, operand between two operators goes to the stronger side:
+
+       a  +  b  *  c  -  d        a  =  b  =  c
+         24 25 26 27 24 25          4  3  4  3
+*/
+
+/// Left and right binding powers of infix operator.
+pub fn infix(c: (u8, u8, u8)) -> Option<(u8, u8)> {
+    match find(c)? {
+        (precedence, Left) => Some((precedence * 2, precedence * 2 + 1)),
+        (precedence, Right) => Some((precedence * 2, precedence * 2 - 1)),
+    }
+}
+
+/// Right binding power of prefix operator.
+pub fn prefix(c: (u8, u8, u8)) -> Option<u8> {
+    match c {
+        (b'-', b' ', b' ') | (b'!', b' ', b' ') => Some(PREFIX_PRECEDENCE * 2),
+        // Range without start, like `..5`.
+        (b'.', b'.', b' ') | (b'.', b'.', b'=') => infix(c).map(|(_, right)| right),
+        _ => None,
+    }
+}
+
+/// Left binding power of postfix operator.
+pub fn postfix(c: (u8, u8, u8)) -> Option<u8> {
+    match c {
+        (b'.', b' ', b' ')
+        | (b'?', b'.', b' ')
+        | (b'?', b' ', b' ')
+        | (b'[', b' ', b' ')
+        | (b'(', b' ', b' ') => Some(POSTFIX_PRECEDENCE * 2),
+        _ => None,
     }
 }
@@ -1,8 +1,17 @@
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Token {
     Identifier,
     Real,
     Integer,
+    /// Character literal, its value is the code point.
+    Char,
     Operator,
+    String,
+    /// String part before first `{` of interpolated string.
+    InterpolationStart,
+    /// String part between `}` and `{` of interpolated string.
+    InterpolationMiddle,
+    /// String part after last `}` of interpolated string.
+    InterpolationEnd,
     Unknown,
 }
@@ -0,0 +1,54 @@
+use std::iter::Peekable;
+use std::ops::RangeInclusive;
+
+/// Decodes UTF-8 bytes to chars. Invalid sequence is returned as
+/// `Err(length)`, where length is the count of bytes it consumed,
+/// so decoding continues after it.
+pub struct Chars<I: Iterator<Item = u8>> {
+    iter: Peekable<I>,
+}
+
+impl<I: Iterator<Item = u8>> From<I> for Chars<I> {
+    fn from(iter: I) -> Self {
+        Self {
+            iter: iter.peekable(),
+        }
+    }
+}
+
+impl<I: Iterator<Item = u8>> Iterator for Chars<I> {
+    type Item = Result<char, usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let c0 = self.iter.next()?;
+        // Length of sequence and valid range of its second byte.
+        let (length, second): (usize, RangeInclusive<u8>) = match c0 {
+            0x00..=0x7F => return Some(Ok(c0 as char)),
+            0xC2..=0xDF => (2, 0x80..=0xBF),
+            0xE0 => (3, 0xA0..=0xBF),
+            0xE1..=0xEC | 0xEE..=0xEF => (3, 0x80..=0xBF),
+            0xED => (3, 0x80..=0x9F),
+            0xF0 => (4, 0x90..=0xBF),
+            0xF1..=0xF3 => (4, 0x80..=0xBF),
+            0xF4 => (4, 0x80..=0x8F),
+            _ => return Some(Err(1)),
+        };
+        let mut value = (c0 & (0x7F >> length)) as u32;
+        for index in 1..length {
+            let valid = if index == 1 {
+                second.clone()
+            } else {
+                0x80..=0xBF
+            };
+            match self.iter.peek() {
+                Some(&c) if valid.contains(&c) => {
+                    self.iter.next();
+                    value = value << 6 | (c & 0x3F) as u32;
+                }
+                // Byte that breaks sequence may start the next char.
+                _ => return Some(Err(index)),
+            }
+        }
+        Some(char::from_u32(value).ok_or(length))
+    }
+}
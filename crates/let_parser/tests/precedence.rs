@@ -0,0 +1,504 @@
+//! Property tests of operator precedence. Random expression trees are
+//! printed with as few parentheses as the reference precedences below
+//! allow, parsed back and compared with the original tree, both by shape
+//! and by value of a small reference evaluator.
+
+use let_ast::{Expression, ExpressionKind, Item, Operator, Span};
+
+/// Linear congruential generator, failures are reproducible by seed.
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) % n
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+/// Reference precedence of binary operator, higher binds tighter,
+/// and whether it is right associative.
+fn reference(operator: &Operator) -> (u8, bool) {
+    match operator {
+        b"?? " => (1, true),
+        b"|| " => (2, false),
+        b"&& " => (3, false),
+        b"<  " | b"<= " | b">  " | b">= " | b"== " | b"!= " => (COMPARISON, false),
+        b".. " | b"..=" => (RANGE, false),
+        b"^  " => (6, false),
+        b"|  " => (7, false),
+        b"&  " => (8, false),
+        b"<< " | b">> " => (9, false),
+        b"+  " | b"-  " => (10, false),
+        b"*  " | b"/  " | b"%  " => (11, false),
+        _ => panic!("Unexpected operator {operator:?}."),
+    }
+}
+
+const COMPARISON: u8 = 4;
+const RANGE: u8 = 5;
+const PREFIX: u8 = 12;
+const ATOM: u8 = 13;
+
+fn precedence(expression: &Expression) -> u8 {
+    match &expression.kind {
+        ExpressionKind::Unary { .. } => PREFIX,
+        ExpressionKind::Binary { operator, .. } => reference(operator).0,
+        ExpressionKind::Comparison { .. } => COMPARISON,
+        ExpressionKind::Range { .. } => RANGE,
+        _ => ATOM,
+    }
+}
+
+fn operator(name: &str) -> Operator {
+    let mut operator = *b"   ";
+    operator[..name.len()].copy_from_slice(name.as_bytes());
+    operator
+}
+
+fn node(kind: ExpressionKind) -> Expression {
+    Expression::new(kind, Span::default())
+}
+
+fn literal(value: u64) -> Expression {
+    node(ExpressionKind::Integer(Box::new([value as u8])))
+}
+
+/// Variable `v` holds void.
+fn void() -> Expression {
+    node(ExpressionKind::Identifier(let_ast::Name {
+        value: Box::new(*b"v"),
+        span: Span::default(),
+    }))
+}
+
+fn unary(name: &str, operand: Expression) -> Expression {
+    node(ExpressionKind::Unary {
+        operator: operator(name),
+        operand: Box::new(operand),
+    })
+}
+
+fn binary(name: &str, left: Expression, right: Expression) -> Expression {
+    node(ExpressionKind::Binary {
+        operator: operator(name),
+        left: Box::new(left),
+        right: Box::new(right),
+    })
+}
+
+fn integer(random: &mut Random, depth: u32) -> Expression {
+    if depth == 0 || random.below(4) == 0 {
+        return literal(random.below(10));
+    }
+    match random.below(8) {
+        0 => unary("-", integer(random, depth - 1)),
+        1 => binary("??", maybe(random, depth - 1), integer(random, depth - 1)),
+        _ => {
+            let name = random.pick(&["+", "-", "*", "/", "%", "<<", ">>", "&", "|", "^"]);
+            binary(name, integer(random, depth - 1), integer(random, depth - 1))
+        }
+    }
+}
+
+/// Integer or void.
+fn maybe(random: &mut Random, depth: u32) -> Expression {
+    if depth == 0 || random.below(3) == 0 {
+        return if random.below(2) == 0 {
+            void()
+        } else {
+            literal(random.below(10))
+        };
+    }
+    match random.below(2) {
+        0 => binary("??", maybe(random, depth - 1), maybe(random, depth - 1)),
+        _ => integer(random, depth),
+    }
+}
+
+fn boolean(random: &mut Random, depth: u32) -> Expression {
+    let depth = depth.saturating_sub(1);
+    match random.below(5) {
+        0 if depth > 0 => unary("!", boolean(random, depth)),
+        1 if depth > 0 => {
+            let name = random.pick(&["&&", "||"]);
+            binary(name, boolean(random, depth), boolean(random, depth))
+        }
+        2 => {
+            let names = random.pick(&["< <=", "> >=", "=="]);
+            let names: Vec<&str> = names.split(' ').collect();
+            let first = Box::new(integer(random, depth));
+            let rest = (0..2 + random.below(2))
+                .map(|_| (operator(random.pick(&names)), integer(random, depth)))
+                .collect();
+            node(ExpressionKind::Comparison { first, rest })
+        }
+        _ => {
+            let name = random.pick(&["<", "<=", ">", ">=", "==", "!="]);
+            binary(name, integer(random, depth), integer(random, depth))
+        }
+    }
+}
+
+fn range(random: &mut Random, depth: u32) -> Expression {
+    let depth = depth.saturating_sub(1);
+    let start = (random.below(4) != 0).then(|| Box::new(integer(random, depth)));
+    let end = (random.below(4) != 0).then(|| Box::new(integer(random, depth)));
+    let inclusive = end.is_some() && random.below(2) == 0;
+    node(ExpressionKind::Range {
+        start,
+        end,
+        inclusive,
+    })
+}
+
+fn any(random: &mut Random, depth: u32) -> Expression {
+    match random.below(5) {
+        0 => boolean(random, depth),
+        1 => range(random, depth),
+        2 if depth > 0 => binary("??", maybe(random, depth - 1), any(random, depth - 1)),
+        _ => integer(random, depth),
+    }
+}
+
+/// Prints operand in parentheses if it binds weaker than `min`.
+/// Range without end is always enclosed, since it must be followed by
+/// closing bracket.
+fn operand(expression: &Expression, min: u8, out: &mut String) {
+    let open = matches!(expression.kind, ExpressionKind::Range { end: None, .. });
+    if precedence(expression) < min || open {
+        out.push_str("( ");
+        print(expression, out);
+        out.push_str(" )");
+    } else {
+        print(expression, out);
+    }
+}
+
+fn name(operator: &Operator) -> String {
+    String::from_utf8_lossy(operator).trim().to_string()
+}
+
+fn print(expression: &Expression, out: &mut String) {
+    match &expression.kind {
+        ExpressionKind::Integer(value) => out.push_str(&value[0].to_string()),
+        ExpressionKind::Identifier(name) => out.push_str(&String::from_utf8_lossy(&name.value)),
+        ExpressionKind::Unary {
+            operator,
+            operand: x,
+        } => {
+            out.push_str(&name(operator));
+            out.push(' ');
+            operand(x, PREFIX, out);
+        }
+        ExpressionKind::Binary {
+            operator,
+            left,
+            right,
+        } => {
+            let (precedence, right_associative) = reference(operator);
+            let (left_min, right_min) = if precedence == COMPARISON {
+                // Comparison operand would continue the chain.
+                (precedence + 1, precedence + 1)
+            } else if right_associative {
+                (precedence + 1, precedence)
+            } else {
+                (precedence, precedence + 1)
+            };
+            operand(left, left_min, out);
+            out.push_str(&format!(" {} ", name(operator)));
+            operand(right, right_min, out);
+        }
+        ExpressionKind::Comparison { first, rest } => {
+            operand(first, COMPARISON + 1, out);
+            for (operator, expression) in rest {
+                out.push_str(&format!(" {} ", name(operator)));
+                operand(expression, COMPARISON + 1, out);
+            }
+        }
+        ExpressionKind::Range {
+            start,
+            end,
+            inclusive,
+        } => {
+            if let Some(start) = start {
+                operand(start, RANGE + 1, out);
+                out.push(' ');
+            }
+            out.push_str(if *inclusive { "..=" } else { ".." });
+            if let Some(end) = end {
+                out.push(' ');
+                operand(end, RANGE + 1, out);
+            }
+        }
+        kind => panic!("Unexpected expression {kind:?}."),
+    }
+}
+
+/// Prints expression with every operation in parentheses, parentheses
+/// of source are dropped.
+fn shape(expression: &Expression) -> String {
+    let part = |expression: &Option<Box<Expression>>| match expression {
+        Some(expression) => shape(expression),
+        None => "_".to_string(),
+    };
+    match &expression.kind {
+        ExpressionKind::Integer(value) => value
+            .iter()
+            .fold(0u64, |value, &byte| value << 8 | byte as u64)
+            .to_string(),
+        ExpressionKind::Identifier(name) => String::from_utf8_lossy(&name.value).into_owned(),
+        ExpressionKind::Paren(expression) => shape(expression),
+        ExpressionKind::List(items) => {
+            let items: Vec<String> = items.iter().map(shape).collect();
+            format!("[{}]", items.join(" "))
+        }
+        ExpressionKind::Index { object, index, .. } => {
+            format!("{}[{}]", shape(object), shape(index))
+        }
+        ExpressionKind::Call { callee, arguments } => {
+            let arguments: Vec<String> = arguments.iter().map(shape).collect();
+            format!("{}({})", shape(callee), arguments.join(" "))
+        }
+        ExpressionKind::Unary { operator, operand } => {
+            format!("({}{})", name(operator), shape(operand))
+        }
+        ExpressionKind::Binary {
+            operator,
+            left,
+            right,
+        } => format!("({} {} {})", shape(left), name(operator), shape(right)),
+        ExpressionKind::Comparison { first, rest } => {
+            let mut result = format!("({}", shape(first));
+            for (operator, expression) in rest {
+                result.push_str(&format!(" {} {}", name(operator), shape(expression)));
+            }
+            result + ")"
+        }
+        ExpressionKind::Range {
+            start,
+            end,
+            inclusive,
+        } => {
+            let operator = if *inclusive { "..=" } else { ".." };
+            format!("({}{operator}{})", part(start), part(end))
+        }
+        kind => panic!("Unexpected expression {kind:?}."),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Value {
+    Void,
+    Integer(i64),
+    Boolean(bool),
+    Range(Option<i64>, Option<i64>, bool),
+}
+
+fn eval_integer(expression: &Expression) -> Option<i64> {
+    match eval(expression)? {
+        Value::Integer(value) => Some(value),
+        _ => None,
+    }
+}
+
+fn eval_boolean(expression: &Expression) -> Option<bool> {
+    match eval(expression)? {
+        Value::Boolean(value) => Some(value),
+        _ => None,
+    }
+}
+
+fn compare(operator: &Operator, l: i64, r: i64) -> bool {
+    match operator {
+        b"<  " => l < r,
+        b"<= " => l <= r,
+        b">  " => l > r,
+        b">= " => l >= r,
+        b"== " => l == r,
+        b"!= " => l != r,
+        _ => panic!("Unexpected operator {operator:?}."),
+    }
+}
+
+/// Reference evaluator, `None` stands for a run time error like division
+/// by zero.
+fn eval(expression: &Expression) -> Option<Value> {
+    let value = match &expression.kind {
+        ExpressionKind::Integer(_) => Value::Integer(shape(expression).parse().ok()?),
+        ExpressionKind::Identifier(_) => Value::Void,
+        ExpressionKind::Paren(expression) => eval(expression)?,
+        ExpressionKind::Unary { operator, operand } => match operator {
+            b"-  " => Value::Integer(eval_integer(operand)?.checked_neg()?),
+            _ => Value::Boolean(!eval_boolean(operand)?),
+        },
+        ExpressionKind::Binary {
+            operator,
+            left,
+            right,
+        } => match operator {
+            b"?? " => match eval(left)? {
+                Value::Void => eval(right)?,
+                value => value,
+            },
+            b"&& " => Value::Boolean(eval_boolean(left)? && eval_boolean(right)?),
+            b"|| " => Value::Boolean(eval_boolean(left)? || eval_boolean(right)?),
+            _ => {
+                let (l, r) = (eval_integer(left)?, eval_integer(right)?);
+                let shift = u32::try_from(r).ok().filter(|&shift| shift < 64);
+                Value::Integer(match operator {
+                    b"+  " => l.checked_add(r)?,
+                    b"-  " => l.checked_sub(r)?,
+                    b"*  " => l.checked_mul(r)?,
+                    b"/  " => l.checked_div(r)?,
+                    b"%  " => l.checked_rem(r)?,
+                    b"<< " => l << shift?,
+                    b">> " => l >> shift?,
+                    b"&  " => l & r,
+                    b"|  " => l | r,
+                    b"^  " => l ^ r,
+                    _ => return Some(Value::Boolean(compare(operator, l, r))),
+                })
+            }
+        },
+        ExpressionKind::Comparison { first, rest } => {
+            let mut l = eval_integer(first)?;
+            let mut result = true;
+            for (operator, expression) in rest {
+                let r = eval_integer(expression)?;
+                result &= compare(operator, l, r);
+                l = r;
+            }
+            Value::Boolean(result)
+        }
+        ExpressionKind::Range {
+            start,
+            end,
+            inclusive,
+        } => {
+            let bound = |bound: &Option<Box<Expression>>| match bound {
+                Some(bound) => eval_integer(bound).map(Some),
+                None => Some(None),
+            };
+            Value::Range(bound(start)?, bound(end)?, *inclusive)
+        }
+        kind => panic!("Unexpected expression {kind:?}."),
+    };
+    Some(value)
+}
+
+fn parse(source: &str) -> Expression {
+    let mut parser = let_parser::Parser::new(source.bytes());
+    let mut module = parser.parse();
+    assert!(
+        parser.errors().is_empty(),
+        "{source}\n{:?}",
+        parser.errors()
+    );
+    match module.items.pop() {
+        Some(Item::Code(expression)) if module.items.is_empty() => expression,
+        items => panic!("{source}\nExpected single expression, found {items:?}."),
+    }
+}
+
+fn value(source: &str) -> Option<Value> {
+    eval(&parse(source))
+}
+
+#[test]
+fn random_expressions() {
+    for seed in 0..3000 {
+        let mut random = Random(seed);
+        let expression = any(&mut random, 4);
+        let mut source = String::new();
+        print(&expression, &mut source);
+        // Range without end is closed by bracket or end of file.
+        let bracketed = random.below(4) == 0;
+        let parsed = if bracketed {
+            match parse(&format!("[ {source} ]")).kind {
+                ExpressionKind::List(mut items) if items.len() == 1 => items.pop().unwrap(),
+                kind => panic!("{source}\nExpected list, found {kind:?}."),
+            }
+        } else {
+            parse(&source)
+        };
+        assert_eq!(shape(&parsed), shape(&expression), "seed {seed}: {source}");
+        assert_eq!(eval(&parsed), eval(&expression), "seed {seed}: {source}");
+    }
+}
+
+#[test]
+fn associativity() {
+    assert_eq!(value("10 - 4 - 3"), Some(Value::Integer(3)));
+    assert_eq!(value("64 / 4 / 2"), Some(Value::Integer(8)));
+    assert_eq!(value("1 << 3 >> 1"), Some(Value::Integer(4)));
+    assert_eq!(value("6 ^ 3 | 1"), Some(Value::Integer(5)));
+    assert_eq!(value("2 + 3 * 4 % 5"), Some(Value::Integer(4)));
+}
+
+#[test]
+fn unary_operators() {
+    assert_eq!(value("- 2 * 3"), Some(Value::Integer(-6)));
+    assert_eq!(value("- - 2 - 3"), Some(Value::Integer(-1)));
+    assert_eq!(shape(&parse("- 1 + 2")), "((-1) + 2)");
+    assert_eq!(shape(&parse("! v ?? 1")), "((!v) ?? 1)");
+    assert_eq!(shape(&parse("! 1 < 2")), "((!1) < 2)");
+    assert_eq!(value("! ( 1 == 2 ) && 1 < 2"), Some(Value::Boolean(true)));
+}
+
+#[test]
+fn coalescing() {
+    assert_eq!(shape(&parse("v ?? v ?? 3")), "(v ?? (v ?? 3))");
+    assert_eq!(value("v ?? v ?? 3"), Some(Value::Integer(3)));
+    assert_eq!(value("v ?? 1 + 2"), Some(Value::Integer(3)));
+    assert_eq!(value("1 ?? 2 < 3"), Some(Value::Integer(1)));
+    assert_eq!(value("v ?? 2 < 3"), Some(Value::Boolean(true)));
+}
+
+#[test]
+fn ranges() {
+    assert_eq!(
+        value("1 + 1 .. 2 * 3"),
+        Some(Value::Range(Some(2), Some(6), false))
+    );
+    assert_eq!(value("..= 1 | 4"), Some(Value::Range(None, Some(5), true)));
+    assert_eq!(value("v ?? 1 .."), Some(Value::Range(Some(1), None, false)));
+    assert_eq!(shape(&parse("[ 1 .. ]")), "[(1.._)]");
+}
+
+#[test]
+fn chained_comparisons() {
+    assert_eq!(shape(&parse("1 < 2 <= 2")), "(1 < 2 <= 2)");
+    assert_eq!(value("1 < 2 <= 2"), Some(Value::Boolean(true)));
+    assert_eq!(value("3 > 2 > 2"), Some(Value::Boolean(false)));
+    assert_eq!(value("1 + 1 == 2 == 4 / 2"), Some(Value::Boolean(true)));
+    assert_eq!(value("0 < 1 && 2 > 1 >= 1"), Some(Value::Boolean(true)));
+}
+
+#[test]
+fn postfix_operators() {
+    assert_eq!(shape(&parse("a[0][1]")), "a[0][1]");
+    assert_eq!(shape(&parse("( 1 ..= 3 )[-1]")), "(1..=3)[(-1)]");
+    assert_eq!(shape(&parse("[ 1 2 ][..1]")), "[1 2][(_..1)]");
+    assert_eq!(shape(&parse("f(1)(2 3)[4]")), "f(1)(2 3)[4]");
+    assert_eq!(shape(&parse("- a[0] * 2")), "((-a[0]) * 2)");
+    assert_eq!(shape(&parse("a[0] .. f(1)")), "(a[0]..f(1))");
+    match parse("let x = a[0][1]").kind {
+        ExpressionKind::Let { value, .. } => assert_eq!(shape(&value), "a[0][1]"),
+        kind => panic!("Expected let, found {kind:?}."),
+    }
+}
+
+#[test]
+fn detached_brackets_are_items() {
+    assert_eq!(shape(&parse("[ a [1] ]")), "[a [1]]");
+    assert_eq!(shape(&parse("[ [1] [2] ]")), "[[1] [2]]");
+    assert_eq!(shape(&parse("[ f (-1) ]")), "[f (-1)]");
+    assert_eq!(shape(&parse("f(x (-1))")), "f(x (-1))");
+}
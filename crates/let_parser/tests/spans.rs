@@ -0,0 +1,21 @@
+use let_ast::{ExpressionKind, Item};
+
+#[test]
+fn branch_spans() {
+    let source = "if 1\n    2\nelif 3\n    4\nelse\n    5\nend\n";
+    let mut parser = let_parser::Parser::new(source.bytes());
+    let module = parser.parse();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    let branches = match &module.items[..] {
+        [Item::Code(expression)] => match &expression.kind {
+            ExpressionKind::If { branches, .. } => branches,
+            kind => panic!("Expected if, found {kind:?}."),
+        },
+        items => panic!("Expected single expression, found {items:?}."),
+    };
+    let spans: Vec<&str> = branches
+        .iter()
+        .map(|branch| &source[branch.span.range()])
+        .collect();
+    assert_eq!(spans, ["if 1\n    2", "elif 3\n    4"]);
+}
@@ -0,0 +1,55 @@
+//! Stable codes of diagnostics. Codes are never reused, so they can be
+//! referenced by tools and documentation.
+
+// Syntax errors.
+pub const UNKNOWN_TOKEN: &str = "L0100";
+pub const EXPECTED_TOKEN: &str = "L0101";
+pub const UNTERMINATED_STRING: &str = "L0102";
+pub const UNEXPECTED_KEYWORD: &str = "L0103";
+pub const INVALID_NUMBER: &str = "L0104";
+pub const TOO_MANY_ARGUMENTS: &str = "L0105";
+pub const INVALID_ASSIGNMENT_TARGET: &str = "L0106";
+pub const UNSUPPORTED_OPERATOR: &str = "L0107";
+pub const COMPARISON_CHAIN: &str = "L0108";
+pub const RANGE_WITHOUT_END: &str = "L0109";
+pub const INVALID_CHARACTER: &str = "L0111";
+
+// Name resolution and code generation.
+pub const UNKNOWN_VARIABLE: &str = "L0200";
+pub const UNKNOWN_FUNCTION: &str = "L0201";
+pub const CONST_ASSIGN: &str = "L0202";
+pub const REDECLARATION: &str = "L0203";
+pub const SHADOW_PARAMETER: &str = "L0204";
+pub const SUPER_OUTSIDE_CLASS: &str = "L0205";
+pub const MISSING_SELF: &str = "L0206";
+pub const CAPTURE_TOO_FAR: &str = "L0207";
+pub const BUILTIN_NOT_CALLED: &str = "L0208";
+pub const TOO_MANY_PARTS: &str = "L0209";
+pub const UNKNOWN_CONSTRUCTOR: &str = "L0210";
+
+// Modules and linking.
+pub const DUPLICATE_LABEL: &str = "L0300";
+pub const CORRUPTED_MODULE: &str = "L0301";
+pub const UNKNOWN_FORMAT: &str = "L0302";
+pub const TOO_MANY_STRINGS: &str = "L0303";
+pub const DUPLICATE_CLASS: &str = "L0304";
+pub const PROGRAM_TOO_BIG: &str = "L0305";
+pub const ARITY_MISMATCH: &str = "L0306";
+pub const UNDEFINED_SYMBOL: &str = "L0307";
+pub const UNSUPPORTED_VERSION: &str = "L0308";
+pub const UNSUPPORTED_FEATURE: &str = "L0309";
+pub const CHECKSUM_MISMATCH: &str = "L0310";
+
+// Tools.
+pub const IO: &str = "L0400";
+pub const INVALID_UTF8: &str = "L0401";
+pub const NO_INPUT: &str = "L0402";
+pub const NO_MAIN: &str = "L0403";
+pub const COMPILATION_FAILED: &str = "L0404";
+
+// Runtime.
+pub const RUNTIME: &str = "L0500";
+
+// Types.
+pub const TYPE_MISMATCH: &str = "L0600";
+pub const UNKNOWN_TYPE: &str = "L0601";
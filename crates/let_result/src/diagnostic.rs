@@ -0,0 +1,110 @@
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "Error"),
+            Severity::Warning => write!(f, "Warning"),
+        }
+    }
+}
+
+/// Secondary location of diagnostic, like original declaration.
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub range: Range<usize>,
+    pub message: String,
+}
+
+/// Error or warning with its location in source file.
+/// Diagnostic is only data, it is rendered by `render`.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable code from `codes`.
+    pub code: &'static str,
+    pub message: String,
+    /// Primary location, byte range of source file.
+    pub range: Option<Range<usize>>,
+    /// Path of source file, if it differs from the file being processed,
+    /// like location of a call found while linking.
+    pub file: Option<String>,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: String) -> Self {
+        Self {
+            severity,
+            code,
+            message,
+            range: None,
+            file: None,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn error(code: &'static str, message: String) -> Self {
+        Self::new(Severity::Error, code, message)
+    }
+
+    pub fn warning(code: &'static str, message: String) -> Self {
+        Self::new(Severity::Warning, code, message)
+    }
+
+    pub fn with_range(mut self, range: Range<usize>) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    pub fn with_file(mut self, path: String) -> Self {
+        self.file = Some(path);
+        self
+    }
+
+    pub fn with_label(mut self, range: Range<usize>, message: String) -> Self {
+        self.labels.push(Label { range, message });
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn with_help(mut self, help: String) -> Self {
+        self.help = Some(help);
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// Diagnostic without source lines, for errors that have no location.
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+        for label in &self.labels {
+            write!(f, "\nNote: {}", label.message)?;
+        }
+        for note in &self.notes {
+            write!(f, "\nNote: {note}")?;
+        }
+        if let Some(help) = &self.help {
+            write!(f, "\nHelp: {help}")?;
+        }
+        Ok(())
+    }
+}
@@ -3,7 +3,7 @@ pub enum Error {
     Utf8(std::str::Utf8Error),
     ParseInt(std::num::ParseIntError),
     ParseFloat(std::num::ParseFloatError),
-    Custom(Box<String>),
+    Diagnostic(Box<crate::Diagnostic>),
     IO(std::io::Error),
     FromUtf8(Box<std::string::FromUtf8Error>),
 }
@@ -38,22 +38,57 @@ impl From<std::string::FromUtf8Error> for Error {
     }
 }
 
+impl From<crate::Diagnostic> for Error {
+    fn from(value: crate::Diagnostic) -> Self {
+        Self::Diagnostic(Box::new(value))
+    }
+}
+
+impl Error {
+    /// Converts error to diagnostic, errors of other libraries get
+    /// a general code of their kind.
+    pub fn into_diagnostic(self) -> crate::Diagnostic {
+        use crate::{codes, Diagnostic};
+        match self {
+            Error::Diagnostic(diagnostic) => *diagnostic,
+            Error::Utf8(error) => Diagnostic::error(codes::INVALID_UTF8, error.to_string()),
+            Error::FromUtf8(error) => Diagnostic::error(codes::INVALID_UTF8, error.to_string()),
+            Error::ParseInt(error) => Diagnostic::error(codes::INVALID_NUMBER, error.to_string()),
+            Error::ParseFloat(error) => Diagnostic::error(codes::INVALID_NUMBER, error.to_string()),
+            Error::IO(error) => Diagnostic::error(codes::IO, error.to_string()),
+        }
+    }
+
+    /// Sets location of error if it has none.
+    pub fn locate(self, range: std::ops::Range<usize>) -> Self {
+        let mut diagnostic = self.into_diagnostic();
+        if diagnostic.range.is_none() {
+            diagnostic.range = Some(range);
+        }
+        diagnostic.into()
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::Utf8(error) => write!(f, "{error}"),
             Error::ParseInt(error) => write!(f, "{error}"),
             Error::ParseFloat(error) => write!(f, "{error}"),
-            Error::Custom(error) => write!(f, "{error}"),
+            Error::Diagnostic(diagnostic) => write!(f, "{diagnostic}"),
             Error::IO(error) => write!(f, "{error}"),
             Error::FromUtf8(error) => write!(f, "{error}"),
         }
     }
 }
 
+/// Returns error with code from `codes` and formatted message.
 #[macro_export]
 macro_rules! raise {
-    ($($arg:tt)*) => {
-        Err($crate::Error::Custom(Box::new(format!($($arg)*))))
+    ($code:expr, $($arg:tt)*) => {
+        Err($crate::Error::Diagnostic(Box::new($crate::Diagnostic::error(
+            $code,
+            format!($($arg)*),
+        ))))
     };
 }
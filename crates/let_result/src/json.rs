@@ -0,0 +1,164 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use crate::{Diagnostic, Position, Severity};
+
+fn string(text: &str, buffer: &mut String) {
+    buffer.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => buffer.push_str("\\\""),
+            '\\' => buffer.push_str("\\\\"),
+            '\n' => buffer.push_str("\\n"),
+            '\r' => buffer.push_str("\\r"),
+            '\t' => buffer.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(buffer, "\\u{:04x}", c as u32).unwrap(),
+            c => buffer.push(c),
+        }
+    }
+    buffer.push('"');
+}
+
+fn range<P>(range: &Range<usize>, position: &mut Option<P>, buffer: &mut String)
+where
+    P: FnMut(usize) -> Position,
+{
+    write!(
+        buffer,
+        "{{\"byte_start\":{},\"byte_end\":{}",
+        range.start, range.end
+    )
+    .unwrap();
+    if let Some(position) = position {
+        let start = position(range.start);
+        let end = position(range.end);
+        write!(
+            buffer,
+            ",\"line_start\":{},\"column_start\":{},\"line_end\":{},\"column_end\":{}",
+            start.line, start.column, end.line, end.column
+        )
+        .unwrap();
+    }
+    buffer.push('}');
+}
+
+/*
+    {"file":"main.let","severity":"error","code":"L0202","message":"...",
+     "range":{"byte_start":26,"byte_end":27,"line_start":2,"column_start":2,
+              "line_end":2,"column_end":3},
+     "labels":[{"message":"...","range":{...}}],"notes":[],"help":null}
+
+Ranges are null if diagnostic has no location, line and column are
+omitted if offsets can't be resolved.
+ */
+/// Writes diagnostic as JSON object on a single line.
+pub fn write<P>(
+    diagnostic: &Diagnostic,
+    file: Option<&str>,
+    mut position: Option<P>,
+    buffer: &mut String,
+) where
+    P: FnMut(usize) -> Position,
+{
+    buffer.push_str("{\"file\":");
+    match file {
+        Some(file) => string(file, buffer),
+        None => buffer.push_str("null"),
+    }
+    buffer.push_str(",\"severity\":");
+    buffer.push_str(match diagnostic.severity {
+        Severity::Error => "\"error\"",
+        Severity::Warning => "\"warning\"",
+    });
+    buffer.push_str(",\"code\":");
+    string(diagnostic.code, buffer);
+    buffer.push_str(",\"message\":");
+    string(&diagnostic.message, buffer);
+    buffer.push_str(",\"range\":");
+    match &diagnostic.range {
+        Some(primary) => range(primary, &mut position, buffer),
+        None => buffer.push_str("null"),
+    }
+    buffer.push_str(",\"labels\":[");
+    for (index, label) in diagnostic.labels.iter().enumerate() {
+        if index > 0 {
+            buffer.push(',');
+        }
+        buffer.push_str("{\"message\":");
+        string(&label.message, buffer);
+        buffer.push_str(",\"range\":");
+        range(&label.range, &mut position, buffer);
+        buffer.push('}');
+    }
+    buffer.push_str("],\"notes\":[");
+    for (index, note) in diagnostic.notes.iter().enumerate() {
+        if index > 0 {
+            buffer.push(',');
+        }
+        string(note, buffer);
+    }
+    buffer.push_str("],\"help\":");
+    match &diagnostic.help {
+        Some(help) => string(help, buffer),
+        None => buffer.push_str("null"),
+    }
+    buffer.push_str("}\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write;
+    use crate::{codes, Diagnostic, SourceFile};
+
+    #[test]
+    fn escaping() {
+        let diagnostic = Diagnostic::error(
+            codes::UNKNOWN_VARIABLE,
+            "Unknown \"a\\b\"\n\tname\u{1} 名前.".to_string(),
+        )
+        .with_help("Use \"let\".".to_string());
+        let mut buffer = String::new();
+        write(
+            &diagnostic,
+            Some("dir\\a\"b.let"),
+            None::<fn(usize) -> _>,
+            &mut buffer,
+        );
+        assert_eq!(
+            buffer,
+            concat!(
+                r#"{"file":"dir\\a\"b.let","severity":"error","code":"L0200","#,
+                r#""message":"Unknown \"a\\b\"\n\tname\u0001 名前.","range":null,"#,
+                r#""labels":[],"notes":[],"help":"Use \"let\"."}"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn ranges_with_positions() {
+        let file = SourceFile::new("main.let", Box::from(&b"let a = 1\r\nb"[..]));
+        let diagnostic = Diagnostic::error(codes::UNKNOWN_VARIABLE, "b".to_string())
+            .with_range(11..12)
+            .with_label(4..5, "a".to_string())
+            .with_note("n\r".to_string());
+        let mut buffer = String::new();
+        write(
+            &diagnostic,
+            None,
+            Some(|offset| file.position(offset)),
+            &mut buffer,
+        );
+        assert_eq!(
+            buffer,
+            concat!(
+                r#"{"file":null,"severity":"error","code":"L0200","message":"b","#,
+                r#""range":{"byte_start":11,"byte_end":12,"line_start":2,"column_start":1,"#,
+                r#""line_end":2,"column_end":2},"labels":[{"message":"a","range":"#,
+                r#"{"byte_start":4,"byte_end":5,"line_start":1,"column_start":5,"#,
+                r#""line_end":1,"column_end":6}}],"notes":["n\r"],"help":null}"#,
+                "\n"
+            )
+        );
+    }
+}
@@ -1,5 +1,13 @@
+pub mod codes;
+mod diagnostic;
 mod error;
+pub mod json;
+mod render;
+mod source;
 
+pub use diagnostic::*;
 pub use error::*;
+pub use render::{render, ErrorFormat};
+pub use source::*;
 
 pub type Result<T = ()> = std::result::Result<T, Error>;
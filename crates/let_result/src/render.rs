@@ -0,0 +1,195 @@
+use core::ops::Range;
+use std::fmt::Write;
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::{json, Diagnostic, Position, SourceFile, SourceMap};
+
+/// Chars of `line` with their byte offsets, invalid UTF-8 sequence
+/// is read as single U+FFFD like in `String::from_utf8_lossy`.
+fn chars(line: &[u8]) -> Vec<(usize, char)> {
+    let mut chars = Vec::new();
+    let mut offset = 0;
+    for chunk in line.utf8_chunks() {
+        chars.extend(chunk.valid().char_indices().map(|(i, c)| (offset + i, c)));
+        offset += chunk.valid().len();
+        if !chunk.invalid().is_empty() {
+            chars.push((offset, char::REPLACEMENT_CHARACTER));
+            offset += chunk.invalid().len();
+        }
+    }
+    chars
+}
+
+/// Prints `line` and marks bytes `start..end` of it by carets,
+/// empty range is marked by a single caret. Carets are aligned by
+/// display width of chars, so wide chars get two of them.
+fn mark_line(line: &[u8], start: usize, end: usize, buffer: &mut String) {
+    let chars = chars(line);
+    buffer.extend(chars.iter().map(|&(_, c)| c));
+    buffer.push('\n');
+    let mut carets = 0;
+    for &(offset, c) in &chars {
+        if offset < start {
+            // Tabs are kept so carets stay under the marked text.
+            if c == '\t' {
+                buffer.push('\t');
+            } else {
+                buffer.extend(std::iter::repeat_n(' ', c.width().unwrap_or(0)));
+            }
+        } else if offset < end {
+            carets += c.width().unwrap_or(0);
+        }
+    }
+    buffer.extend(std::iter::repeat_n('^', carets.max(1)));
+    buffer.push('\n');
+}
+
+/// Prints source lines covered by `range` with the range marked by carets.
+fn mark(file: &SourceFile, range: Range<usize>, buffer: &mut String) {
+    let first = file.line_number(range.start);
+    // Range ending right after line break doesn't cover the next line.
+    let last = file
+        .line_number(range.end.max(range.start + 1) - 1)
+        .max(first);
+    if first == last {
+        writeln!(buffer, "File \"{}\", line: {first}:", file.path()).unwrap();
+    } else {
+        writeln!(buffer, "File \"{}\", lines: {first}-{last}:", file.path()).unwrap();
+    }
+    for number in first..=last {
+        let line = file.line_range(number);
+        let start = range.start.clamp(line.start, line.end) - line.start;
+        let end = range.end.clamp(line.start, line.end) - line.start;
+        mark_line(file.line(number), start, end.max(start), buffer);
+    }
+}
+
+/// Renders diagnostic with marked lines of source `file`.
+pub fn render(diagnostic: &Diagnostic, file: &SourceFile, buffer: &mut String) {
+    writeln!(
+        buffer,
+        "{}[{}]: {}",
+        diagnostic.severity, diagnostic.code, diagnostic.message
+    )
+    .unwrap();
+    if let Some(range) = &diagnostic.range {
+        mark(file, range.clone(), buffer);
+    }
+    for label in &diagnostic.labels {
+        mark(file, label.range.clone(), buffer);
+        writeln!(buffer, "Note: {}", label.message).unwrap();
+    }
+    for note in &diagnostic.notes {
+        writeln!(buffer, "Note: {note}").unwrap();
+    }
+    if let Some(help) = &diagnostic.help {
+        writeln!(buffer, "Help: {help}").unwrap();
+    }
+}
+
+/// How tools print diagnostics.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum ErrorFormat {
+    /// Text with marked source lines.
+    #[default]
+    Human,
+    /// One JSON object per line for editors and other tools.
+    Json,
+}
+
+impl ErrorFormat {
+    /// Parses value of `--error-format` option.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "human" => Some(Self::Human),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// Renders diagnostic located in source `file`.
+    pub fn render(self, diagnostic: &Diagnostic, file: &SourceFile, buffer: &mut String) {
+        match self {
+            Self::Human => render(diagnostic, file, buffer),
+            Self::Json => json::write(
+                diagnostic,
+                Some(file.path()),
+                Some(|offset| file.position(offset)),
+                buffer,
+            ),
+        }
+    }
+
+    /// Renders diagnostic which refers to its source file by path, the file
+    /// is loaded if needed. Diagnostic without readable file is plain.
+    pub fn render_in(self, diagnostic: &Diagnostic, sources: &mut SourceMap) -> String {
+        let file = diagnostic
+            .file
+            .as_deref()
+            .and_then(|path| sources.find(path).or_else(|| sources.load(path).ok()));
+        match file {
+            Some(file) => {
+                let mut buffer = String::new();
+                self.render(diagnostic, sources.get(file), &mut buffer);
+                buffer
+            }
+            None => self.render_plain(diagnostic),
+        }
+    }
+
+    /// Renders diagnostic which has no source file, like I/O error.
+    pub fn render_plain(self, diagnostic: &Diagnostic) -> String {
+        match self {
+            Self::Human => format!("{diagnostic}\n"),
+            Self::Json => {
+                let mut buffer = String::new();
+                json::write(diagnostic, None, None::<fn(usize) -> Position>, &mut buffer);
+                buffer
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render;
+    use crate::{codes, Diagnostic, SourceFile};
+
+    /// Renders error at the first occurrence of `marked` in `text`.
+    fn marked(text: &str, marked: &str) -> String {
+        let file = SourceFile::new("main.let", Box::from(text.as_bytes()));
+        let start = text.find(marked).unwrap();
+        let diagnostic = Diagnostic::error(codes::UNKNOWN_VARIABLE, "Unknown.".to_string())
+            .with_range(start..start + marked.len());
+        let mut buffer = String::new();
+        render(&diagnostic, &file, &mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn carets_after_wide_chars() {
+        // Each of 名前日本 takes two columns.
+        let indent = " ".repeat("let ".len() + 4 + " = ".len() + 4 + " + ".len());
+        assert_eq!(
+            marked("let 名前 = 日本 + x", "x"),
+            format!("Error[L0200]: Unknown.\nFile \"main.let\", line: 1:\nlet 名前 = 日本 + x\n{indent}^\n")
+        );
+    }
+
+    #[test]
+    fn wide_chars_get_two_carets() {
+        assert_eq!(
+            marked("a = 名前", "名前"),
+            "Error[L0200]: Unknown.\nFile \"main.let\", line: 1:\na = 名前\n    ^^^^\n"
+        );
+    }
+
+    #[test]
+    fn tabs_are_kept() {
+        assert_eq!(
+            marked("\té = y", "y"),
+            "Error[L0200]: Unknown.\nFile \"main.let\", line: 1:\n\té = y\n\t    ^\n"
+        );
+    }
+}
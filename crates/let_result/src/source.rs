@@ -0,0 +1,176 @@
+use std::ops::Range;
+
+/// Line and column of byte offset, both start from 1.
+/// Column counts UTF-8 characters, not bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Counts characters of UTF-8 text, continuation bytes are skipped.
+pub fn count_chars(text: &[u8]) -> usize {
+    text.iter().filter(|&&c| c & 0xC0 != 0x80).count()
+}
+
+/// Loaded source file with offsets of its lines.
+pub struct SourceFile {
+    path: String,
+    text: Box<[u8]>,
+    /// Offset of the start of each line, the first line starts at 0.
+    lines: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn new(path: &str, text: Box<[u8]>) -> Self {
+        let mut lines = vec![0];
+        lines.extend(
+            text.iter()
+                .enumerate()
+                .filter(|(_, &c)| c == b'\n')
+                .map(|(offset, _)| offset + 1),
+        );
+        Self {
+            path: path.to_string(),
+            text,
+            lines,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+
+    /// Number of line containing `offset`, starts from 1.
+    pub fn line_number(&self, offset: usize) -> usize {
+        self.lines.partition_point(|&start| start <= offset)
+    }
+
+    /// Byte range of line `number` without line break.
+    pub fn line_range(&self, number: usize) -> Range<usize> {
+        let start = self.lines[number - 1];
+        let mut end = self
+            .lines
+            .get(number)
+            .map_or(self.text.len(), |&next| next - 1);
+        if end > start && self.text[end - 1] == b'\r' {
+            end -= 1;
+        }
+        start..end
+    }
+
+    /// Text of line `number` without line break.
+    pub fn line(&self, number: usize) -> &[u8] {
+        &self.text[self.line_range(number)]
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_number(offset);
+        let start = self.lines[line - 1];
+        Position {
+            line,
+            column: count_chars(&self.text[start..offset]) + 1,
+        }
+    }
+
+    /// Positions of the start and the end of `range`.
+    pub fn resolve(&self, range: &Range<usize>) -> (Position, Position) {
+        (self.position(range.start), self.position(range.end))
+    }
+}
+
+/// Identifier of file in `SourceMap`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileId(u32);
+
+/// Source files loaded by a tool, diagnostics are rendered from here
+/// so files are never read twice.
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, path: &str, text: Box<[u8]>) -> FileId {
+        self.files.push(SourceFile::new(path, text));
+        FileId(self.files.len() as u32 - 1)
+    }
+
+    /// Reads file `path` from disk.
+    pub fn load(&mut self, path: &str) -> crate::Result<FileId> {
+        match std::fs::read(path) {
+            Ok(text) => Ok(self.add(path, text.into_boxed_slice())),
+            Err(error) => crate::raise!(
+                crate::codes::IO,
+                "Unable to open file \"{path}\", error: {error}"
+            ),
+        }
+    }
+
+    /// Finds loaded file by its path.
+    pub fn find(&self, path: &str) -> Option<FileId> {
+        let index = self.files.iter().position(|file| file.path() == path)?;
+        Some(FileId(index as u32))
+    }
+
+    pub fn get(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0 as usize]
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &SourceFile> {
+        self.files.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_chars, Position, SourceMap};
+
+    fn position(line: usize, column: usize) -> Position {
+        Position { line, column }
+    }
+
+    #[test]
+    fn span_over_lines() {
+        let mut sources = SourceMap::new();
+        let text = "fn f()\r\n    \"é\n  x\"\nend";
+        let file = sources.add("main.let", Box::from(text.as_bytes()));
+        let file = sources.get(file);
+        let start = text.find('"').unwrap();
+        let end = text.rfind('"').unwrap() + 1;
+        assert_eq!(
+            file.resolve(&(start..end)),
+            (position(2, 5), position(3, 5))
+        );
+        // Line break belongs to the line it ends.
+        assert_eq!(file.position(6), position(1, 7));
+        assert_eq!(file.position(8), position(2, 1));
+        // Column counts chars, `é` is two bytes.
+        assert_eq!(file.position(start + 3), position(2, 7));
+        assert_eq!(file.position(text.len() + 5), position(4, 4));
+        assert_eq!(file.line(1), b"fn f()");
+        assert_eq!(file.line(2), "    \"é".as_bytes());
+        assert_eq!(file.line(4), b"end");
+        assert_eq!(count_chars("\"é".as_bytes()), 2);
+    }
+
+    #[test]
+    fn files_are_found_by_path() {
+        let mut sources = SourceMap::new();
+        let a = sources.add("a.let", Box::from(&b"1"[..]));
+        let b = sources.add("b.let", Box::from(&b"2\n3"[..]));
+        assert_eq!(sources.find("b.let"), Some(b));
+        assert_eq!(sources.find("a.let"), Some(a));
+        assert_eq!(sources.find("c.let"), None);
+        assert_eq!(sources.get(b).position(2), position(2, 1));
+    }
+}
@@ -0,0 +1,492 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::{Add, Mul, Neg, Shl, Shr, Sub};
+
+/// Integer of arbitrary size, integer operations promote their result
+/// to it when it doesn't fit into `i64`.
+///
+/// Magnitude is stored as base 2^32 digits, the least significant first,
+/// without leading zero digits. Zero has no digits and is never negative.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+
+fn compare(l: &[u32], r: &[u32]) -> Ordering {
+    l.len()
+        .cmp(&r.len())
+        .then_with(|| l.iter().rev().cmp(r.iter().rev()))
+}
+
+fn add(l: &[u32], r: &[u32]) -> Vec<u32> {
+    let (long, short) = if l.len() >= r.len() { (l, r) } else { (r, l) };
+    let mut digits = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0;
+    for (i, &digit) in long.iter().enumerate() {
+        let sum = digit as u64 + *short.get(i).unwrap_or(&0) as u64 + carry;
+        digits.push(sum as u32);
+        carry = sum >> 32;
+    }
+    digits.push(carry as u32);
+    digits
+}
+
+/// Subtracts magnitudes, `l` must not be less than `r`.
+fn sub(l: &[u32], r: &[u32]) -> Vec<u32> {
+    let mut digits = Vec::with_capacity(l.len());
+    let mut borrow = 0;
+    for (i, &digit) in l.iter().enumerate() {
+        let difference = digit as i64 - *r.get(i).unwrap_or(&0) as i64 - borrow;
+        digits.push(difference.rem_euclid(1 << 32) as u32);
+        borrow = (difference < 0) as i64;
+    }
+    digits
+}
+
+fn mul(l: &[u32], r: &[u32]) -> Vec<u32> {
+    let mut digits = vec![0; l.len() + r.len()];
+    for (i, &a) in l.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &b) in r.iter().enumerate() {
+            let product = a as u64 * b as u64 + digits[i + j] as u64 + carry;
+            digits[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        digits[i + r.len()] = carry as u32;
+    }
+    digits
+}
+
+/// Divides magnitude by single digit, returns quotient and remainder.
+fn div_rem_digit(l: &[u32], r: u32) -> (Vec<u32>, u32) {
+    let mut digits = vec![0; l.len()];
+    let mut remainder = 0u64;
+    for (i, &digit) in l.iter().enumerate().rev() {
+        let value = remainder << 32 | digit as u64;
+        digits[i] = (value / r as u64) as u32;
+        remainder = value % r as u64;
+    }
+    (digits, remainder as u32)
+}
+
+/// Shifts magnitude left by `shift` bits, less than 32, result has one
+/// more digit.
+fn shl_bits(digits: &[u32], shift: u32) -> Vec<u32> {
+    let mut result = Vec::with_capacity(digits.len() + 1);
+    let mut carry = 0;
+    for &digit in digits {
+        let value = (digit as u64) << shift | carry;
+        result.push(value as u32);
+        carry = value >> 32;
+    }
+    result.push(carry as u32);
+    result
+}
+
+/// Shifts magnitude right by `shift` bits, less than 32.
+fn shr_bits(digits: &[u32], shift: u32) -> Vec<u32> {
+    (0..digits.len())
+        .map(|i| {
+            let high = *digits.get(i + 1).unwrap_or(&0) as u64;
+            ((high << 32 | digits[i] as u64) >> shift) as u32
+        })
+        .collect()
+}
+
+/*
+   Long division of magnitudes by digits, Knuth's algorithm D. Divisor is
+   normalized so its top digit has the high bit set, then each quotient
+   digit estimated from the top digits of the remainder is at most 2 too
+   big. The estimate is corrected by the second digit of divisor and, in
+   rare cases, by adding divisor back after subtraction.
+*/
+fn div_rem(l: &[u32], r: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if let [digit] = r {
+        let (quotient, remainder) = div_rem_digit(l, *digit);
+        return (quotient, vec![remainder]);
+    }
+    if compare(l, r) == Ordering::Less {
+        return (Vec::new(), l.to_vec());
+    }
+    let shift = r.last().unwrap().leading_zeros();
+    let mut v = shl_bits(r, shift);
+    v.pop();
+    let mut u = shl_bits(l, shift);
+    let n = v.len();
+    let (top, second) = (v[n - 1] as u64, v[n - 2] as u64);
+    let mut quotient = vec![0; u.len() - n];
+    for j in (0..quotient.len()).rev() {
+        let numerator = (u[j + n] as u64) << 32 | u[j + n - 1] as u64;
+        let mut estimate = numerator / top;
+        let mut rest = numerator % top;
+        while estimate >> 32 != 0 || estimate * second > (rest << 32 | u[j + n - 2] as u64) {
+            estimate -= 1;
+            rest += top;
+            if rest >> 32 != 0 {
+                break;
+            }
+        }
+
+        // u[j..=j + n] -= estimate * v
+        let mut borrow = 0;
+        let mut carry = 0;
+        for i in 0..n {
+            let product = estimate * v[i] as u64 + carry;
+            carry = product >> 32;
+            let difference = u[i + j] as i64 - (product as u32) as i64 + borrow;
+            u[i + j] = difference as u32;
+            borrow = difference >> 32;
+        }
+        let difference = u[j + n] as i64 - carry as i64 + borrow;
+        u[j + n] = difference as u32;
+
+        if difference < 0 {
+            estimate -= 1;
+            let mut carry = 0;
+            for i in 0..n {
+                let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                u[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+        quotient[j] = estimate as u32;
+    }
+    (quotient, shr_bits(&u[..n], shift))
+}
+
+impl BigInt {
+    fn new(negative: bool, mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Self {
+            negative: negative && !digits.is_empty(),
+            digits,
+        }
+    }
+
+    /// Builds non-negative integer from big-endian bytes.
+    pub fn from_be_bytes(bytes: &[u8]) -> Self {
+        let digits = bytes
+            .rchunks(4)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .fold(0, |digit, &byte| digit << 8 | byte as u32)
+            })
+            .collect();
+        Self::new(false, digits)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// Returns value if it fits into `i64`.
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.digits.len() > 2 {
+            return None;
+        }
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0, |magnitude, &digit| magnitude << 32 | digit as u64);
+        if self.negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        }
+    }
+
+    /// Nearest real value, it is infinite if the integer is too big.
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self.digits.iter().rev().fold(0.0, |magnitude, &digit| {
+            magnitude * 4294967296.0 + digit as f64
+        });
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    /// Truncating division like `i64` one, so remainder has the sign
+    /// of `self`. Returns `None` if `other` is zero.
+    pub fn div_rem(&self, other: &BigInt) -> Option<(BigInt, BigInt)> {
+        if other.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = div_rem(&self.digits, &other.digits);
+        Some((
+            BigInt::new(self.negative != other.negative, quotient),
+            BigInt::new(self.negative, remainder),
+        ))
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let magnitude = value.unsigned_abs();
+        Self::new(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, add(&self.digits, &other.digits));
+        }
+        match compare(&self.digits, &other.digits) {
+            Ordering::Less => BigInt::new(other.negative, sub(&other.digits, &self.digits)),
+            _ => BigInt::new(self.negative, sub(&self.digits, &other.digits)),
+        }
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.digits.clone())
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        BigInt::new(
+            self.negative != other.negative,
+            mul(&self.digits, &other.digits),
+        )
+    }
+}
+
+/// Multiplies by 2 to the power of `shift`.
+impl Shl<u32> for &BigInt {
+    type Output = BigInt;
+
+    fn shl(self, shift: u32) -> BigInt {
+        let mut digits = vec![0; (shift / 32) as usize];
+        digits.extend(shl_bits(&self.digits, shift % 32));
+        BigInt::new(self.negative, digits)
+    }
+}
+
+/// Divides by 2 to the power of `shift` rounding toward negative infinity,
+/// like shift of `i64`.
+impl Shr<u32> for &BigInt {
+    type Output = BigInt;
+
+    fn shr(self, shift: u32) -> BigInt {
+        let skipped = (shift / 32) as usize;
+        let Some(digits) = self
+            .digits
+            .get(skipped..)
+            .filter(|digits| !digits.is_empty())
+        else {
+            return BigInt::from(-(self.negative as i64));
+        };
+        let magnitude = BigInt::new(false, shr_bits(digits, shift % 32));
+        if !self.negative {
+            return magnitude;
+        }
+        // Magnitude of negative value is rounded up if any lost bit is set.
+        let lost = self.digits[..skipped].iter().any(|&digit| digit != 0)
+            || digits[0] & ((1 << (shift % 32)) - 1) != 0;
+        if lost {
+            -&(&magnitude + &BigInt::from(1))
+        } else {
+            -&magnitude
+        }
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare(&self.digits, &other.digits),
+            (true, true) => compare(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Groups of 9 decimal digits, the least significant first.
+        let mut groups = Vec::new();
+        let mut digits = self.digits.clone();
+        while !digits.is_empty() {
+            let (quotient, remainder) = div_rem_digit(&digits, 1_000_000_000);
+            groups.push(remainder);
+            digits = BigInt::new(false, quotient).digits;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut groups = groups.iter().rev();
+        write!(f, "{}", groups.next().unwrap())?;
+        for group in groups {
+            write!(f, "{group:09}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(value: i128) -> BigInt {
+        let magnitude = BigInt::from_be_bytes(&value.unsigned_abs().to_be_bytes());
+        if value < 0 {
+            -&magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    fn pow(base: i64, exponent: u32) -> BigInt {
+        (0..exponent).fold(BigInt::from(1), |value, _| &value * &BigInt::from(base))
+    }
+
+    fn check(l: i128, r: i128) {
+        let (quotient, remainder) = big(l).div_rem(&big(r)).unwrap();
+        assert_eq!(quotient, big(l / r), "{l} / {r}");
+        assert_eq!(remainder, big(l % r), "{l} % {r}");
+    }
+
+    #[test]
+    fn signs() {
+        for (l, r) in [(7, 2), (-7, 2), (7, -2), (-7, -2), (6, 3), (-6, 3), (1, -5)] {
+            check(l, r);
+            check(l << 70, r);
+            check(l << 70, r << 40);
+        }
+        let (quotient, remainder) = big(-1).div_rem(&big(1 << 40)).unwrap();
+        assert!(quotient.is_zero() && !quotient.is_negative());
+        assert!(!remainder.is_zero() && remainder.is_negative());
+    }
+
+    #[test]
+    fn zero() {
+        assert_eq!(big(5).div_rem(&big(0)), None);
+        assert_eq!(big(0).div_rem(&big(0)), None);
+        let (quotient, remainder) = big(0).div_rem(&big(-(1 << 80))).unwrap();
+        assert!(quotient.is_zero() && !quotient.is_negative());
+        assert!(remainder.is_zero() && !remainder.is_negative());
+    }
+
+    #[test]
+    fn multiple_digits() {
+        // Estimates of quotient digits need correction or add back.
+        check(
+            0x7fffffff_80000000_00000000_00000000,
+            0x80000000_00000000_00000001,
+        );
+        check(0x80000000_00000000_00000003, 0x20000000_00000000_00000001);
+        check(0x8000_00000000_0000fffe_00000000, 0x8000_00000000_0000ffff);
+        let mut seed = 1u64;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            seed
+        };
+        for _ in 0..1000 {
+            let l = ((next() as i128) << 64 | next() as i128) >> (next() % 64);
+            let r = ((next() as i128) << 64 | next() as i128) >> (next() % 128);
+            if r != 0 {
+                check(l, r);
+            }
+        }
+    }
+
+    #[test]
+    fn shifts() {
+        for value in [0i128, 1, -1, 5, -5, 0x1234_5678_9abc_def0, -(1 << 100) - 1] {
+            for shift in [0, 1, 31, 32, 33, 64, 100, 126] {
+                if shift < 127 - (128 - value.unsigned_abs().leading_zeros()) {
+                    assert_eq!(
+                        &big(value) << shift,
+                        big(value << shift),
+                        "{value} << {shift}"
+                    );
+                }
+                assert_eq!(
+                    &big(value) >> shift,
+                    big(value >> shift),
+                    "{value} >> {shift}"
+                );
+            }
+        }
+        assert_eq!(&big(-5) >> 1000, big(-1));
+        assert_eq!(&big(5) >> 1000, big(0));
+        assert_eq!(
+            (&big(3) << 200).to_string(),
+            (&pow(2, 200) * &big(3)).to_string()
+        );
+    }
+
+    #[test]
+    fn beyond_i128() {
+        let l = pow(3, 200);
+        let r = &pow(7, 50) + &BigInt::from(12345);
+        let (quotient, remainder) = l.div_rem(&r).unwrap();
+        assert_eq!(
+            quotient.to_string(),
+            "147689269781346654697366079240021362540968891926345127"
+        );
+        assert_eq!(
+            remainder.to_string(),
+            "1480513908709133232415680991351079358637563"
+        );
+        let (quotient, remainder) = (-&l).div_rem(&r).unwrap();
+        assert_eq!(
+            quotient.to_string(),
+            "-147689269781346654697366079240021362540968891926345127"
+        );
+        assert_eq!(
+            remainder.to_string(),
+            "-1480513908709133232415680991351079358637563"
+        );
+        // Quotient times divisor plus remainder gives dividend back.
+        for exponent in [64, 65, 96, 127, 128, 200] {
+            let r = &pow(2, exponent) - &BigInt::from(3);
+            let (quotient, remainder) = l.div_rem(&r).unwrap();
+            assert_eq!(&(&quotient * &r) + &remainder, l);
+            assert!(compare(&remainder.digits, &r.digits) == Ordering::Less);
+        }
+        let (quotient, remainder) = r.div_rem(&l).unwrap();
+        assert!(quotient.is_zero());
+        assert_eq!(remainder, r);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::Value;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// Parsed `{index:fill align width .precision}` placeholder.
+struct Spec {
+    index: Option<usize>,
+    fill: char,
+    align: Option<Align>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+fn align(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    }
+}
+
+fn number(chars: &[char], i: &mut usize) -> Option<usize> {
+    let start = *i;
+    while *i < chars.len() && chars[*i].is_ascii_digit() {
+        *i += 1;
+    }
+    if start == *i {
+        None
+    } else {
+        chars[start..*i].iter().collect::<String>().parse().ok()
+    }
+}
+
+fn parse_spec(placeholder: &str) -> Result<Spec, String> {
+    let chars: Vec<char> = placeholder.chars().collect();
+    let mut i = 0;
+    let index = number(&chars, &mut i);
+    let mut spec = Spec {
+        index,
+        fill: ' ',
+        align: None,
+        width: 0,
+        precision: None,
+    };
+
+    if i < chars.len() {
+        if chars[i] != ':' {
+            return Err(format!("Invalid format placeholder {{{placeholder}}}."));
+        }
+        i += 1;
+
+        if i + 1 < chars.len() && align(chars[i + 1]).is_some() {
+            spec.fill = chars[i];
+            spec.align = align(chars[i + 1]);
+            i += 2;
+        } else if i < chars.len() && align(chars[i]).is_some() {
+            spec.align = align(chars[i]);
+            i += 1;
+        }
+
+        spec.width = number(&chars, &mut i).unwrap_or(0);
+
+        if i < chars.len() && chars[i] == '.' {
+            i += 1;
+            spec.precision = number(&chars, &mut i);
+            if spec.precision.is_none() {
+                return Err(format!("Expected precision in {{{placeholder}}}."));
+            }
+        }
+
+        if i != chars.len() {
+            return Err(format!("Invalid format placeholder {{{placeholder}}}."));
+        }
+    }
+
+    Ok(spec)
+}
+
+fn format_value(spec: &Spec, value: &Value, output: &mut String) {
+    let text = match (value, spec.precision) {
+        (Value::Real(value), Some(precision)) => format!("{value:.precision$}"),
+        (Value::Integer(_) | Value::BigInt(_) | Value::Real(_), _) => value.to_string(),
+        (_, Some(precision)) => value.to_string().chars().take(precision).collect(),
+        _ => value.to_string(),
+    };
+
+    let align = spec.align.unwrap_or(match value {
+        Value::Integer(_) | Value::BigInt(_) | Value::Real(_) => Align::Right,
+        _ => Align::Left,
+    });
+
+    let padding = spec.width.saturating_sub(text.chars().count());
+    let (left, right) = match align {
+        Align::Left => (0, padding),
+        Align::Right => (padding, 0),
+        Align::Center => (padding / 2, padding - padding / 2),
+    };
+
+    output.extend(std::iter::repeat_n(spec.fill, left));
+    output.push_str(&text);
+    output.extend(std::iter::repeat_n(spec.fill, right));
+}
+
+/// Formats arguments by format string like `"{:>8.2} {0}"`.
+/// Use `{{` and `}}` to write braces.
+pub fn format(format: &str, args: &[Value]) -> Result<String, String> {
+    let mut output = String::new();
+    let mut chars = format.chars().peekable();
+    let mut next_index = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err("Expected '}' in format string.".to_string()),
+                    }
+                }
+                let spec = parse_spec(&placeholder)?;
+                let index = spec.index.unwrap_or_else(|| {
+                    next_index += 1;
+                    next_index - 1
+                });
+                match args.get(index) {
+                    Some(value) => format_value(&spec, value, &mut output),
+                    None => {
+                        return Err(format!(
+                            "Format argument {index} is missing, found {} arguments.",
+                            args.len()
+                        ))
+                    }
+                }
+            }
+            '}' => return Err("Unmatched '}' in format string.".to_string()),
+            c => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format;
+    use crate::Value;
+
+    #[test]
+    fn positional_placeholders() {
+        let args = [Value::string("a".into()), Value::string("b".into())];
+        assert_eq!(format("{0} {1} {0}", &args).unwrap(), "a b a");
+        assert_eq!(format("{} {} {1}", &args).unwrap(), "a b b");
+    }
+
+    #[test]
+    fn placeholders_with_spec() {
+        let args = [Value::Integer(7), Value::Real(2.5678)];
+        assert_eq!(
+            format("{0:>4}|{0:<4}|{0:*^5}", &args).unwrap(),
+            "   7|7   |**7**"
+        );
+        assert_eq!(format("{1:.2} {1:8.3}", &args).unwrap(), "2.57    2.568");
+    }
+
+    #[test]
+    fn invalid_placeholders() {
+        let args = [Value::Integer(1)];
+        assert!(format("{1}", &args).is_err());
+        assert!(format("{0:x}", &args).is_err());
+        assert!(format("{0", &args).is_err());
+    }
+}
@@ -1,5 +1,12 @@
 use core::fmt;
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+mod bigint;
+mod format;
+mod range;
+
+pub use bigint::BigInt;
+pub use range::{Range, RangeIter};
 
 const DUMP_OPCODE: bool = true;
 const DUMP_STACK: bool = true;
@@ -34,13 +41,29 @@ fn fetch_u32(opcodes: &[u8], offset: u32) -> VMResult<u32> {
     ]))
 }
 
+/// Saved state of caller, stored in stack before function arguments.
+#[derive(Clone, Copy)]
+pub struct CallState {
+    pub pc: u32,
+    pub locals: u32,
+    /// Unique id of called function frame.
+    pub frame: u32,
+    /// Locals of enclosing function frame for local functions.
+    pub link: u32,
+}
+
 #[derive(Clone)]
 pub enum Value {
     Void,
     Boolean(bool),
     Integer(i64),
+    /// Integer which doesn't fit into `i64`.
+    BigInt(Rc<BigInt>),
+    Real(f64),
     Address(u32),
-    CallState(u32, u32),
+    CallState(CallState),
+    /// Local function address with locals and id of enclosing frame.
+    Closure(u32, u32, u32),
     Object(Rc<RefCell<Object>>),
 }
 
@@ -50,21 +73,144 @@ impl fmt::Display for Value {
             Value::Void => write!(f, "()"),
             Value::Boolean(value) => write!(f, "{value}"),
             Value::Integer(value) => write!(f, "{value}"),
+            Value::BigInt(value) => write!(f, "{value}"),
+            Value::Real(value) => write!(f, "{value}"),
             Value::Address(value) => write!(f, "{value}"),
-            Value::CallState(pc, locals) => write!(f, "(PC:{pc} LC:{locals})"),
+            Value::CallState(state) => write!(f, "(PC:{} LC:{})", state.pc, state.locals),
+            Value::Closure(address, locals, _) => write!(f, "{address}(LC:{locals})"),
             Value::Object(object) => write!(f, "{}", object.borrow()),
         }
     }
 }
 
+impl Value {
+    /// Integer value, it is `Integer` if it fits into `i64`.
+    fn big(value: BigInt) -> Self {
+        match value.to_i64() {
+            Some(value) => Value::Integer(value),
+            None => Value::BigInt(Rc::new(value)),
+        }
+    }
+
+    /// Real value of number, integers are converted.
+    fn to_real(&self) -> Option<f64> {
+        match self {
+            Value::Integer(value) => Some(*value as f64),
+            Value::BigInt(value) => Some(value.to_f64()),
+            Value::Real(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn to_big(&self) -> Option<BigInt> {
+        match self {
+            Value::Integer(value) => Some(BigInt::from(*value)),
+            Value::BigInt(value) => Some((**value).clone()),
+            _ => None,
+        }
+    }
+
+    /// Name of value type as it is written in type annotations.
+    fn type_name(&self) -> String {
+        match self {
+            Value::Void => "void".into(),
+            Value::Boolean(_) => "bool".into(),
+            Value::Integer(_) | Value::BigInt(_) => "int".into(),
+            Value::Real(_) => "real".into(),
+            Value::Address(_) | Value::Closure(..) => "function".into(),
+            Value::CallState(_) => "call state".into(),
+            Value::Object(object) => match &*object.borrow() {
+                Object::List(_) => "list".into(),
+                Object::Range(_) => "range".into(),
+                Object::String(_) => "str".into(),
+                Object::Instance(instance) => instance.class.name.clone(),
+            },
+        }
+    }
+
+    fn string(value: String) -> Self {
+        Value::Object(Rc::new(RefCell::new(Object::String(value))))
+    }
+
+    fn is_string(&self) -> bool {
+        match self {
+            Value::Object(object) => matches!(&*object.borrow(), Object::String(_)),
+            _ => false,
+        }
+    }
+
+    fn range(&self) -> Option<Range> {
+        match self {
+            Value::Object(object) => match &*object.borrow() {
+                Object::Range(range) => Some(*range),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Class loaded from module class descriptors.
+pub struct Class {
+    pub name: String,
+    pub parent: Option<Rc<Class>>,
+    /// Method addresses by name, inherited methods are not included.
+    pub methods: HashMap<String, u32>,
+}
+
+impl Class {
+    /// Finds method in class or its ancestors.
+    pub fn method(&self, name: &str) -> Option<u32> {
+        match self.methods.get(name) {
+            Some(&address) => Some(address),
+            None => self.parent.as_ref()?.method(name),
+        }
+    }
+
+    /// Checks if class is `name` or inherits from it.
+    pub fn is(&self, name: &str) -> bool {
+        self.name == name || self.parent.as_ref().is_some_and(|parent| parent.is(name))
+    }
+}
+
+pub struct Instance {
+    pub class: Rc<Class>,
+    /// Fields in order of assignment.
+    pub fields: Vec<(String, Value)>,
+}
+
+impl Instance {
+    fn get(&self, name: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+    }
+
+    fn set(&mut self, name: &str, value: Value) {
+        match self.fields.iter_mut().find(|(field, _)| field == name) {
+            Some((_, field)) => *field = value,
+            None => self.fields.push((name.to_string(), value)),
+        }
+    }
+}
+
 pub enum Object {
     List(Vec<Value>),
+    Range(Range),
+    String(String),
+    Instance(Instance),
 }
 
 impl Object {
-    fn push(&mut self, value: Value) {
+    /// Returns false if object can't hold values.
+    fn push(&mut self, value: Value) -> bool {
         match self {
-            Object::List(list) => list.push(value),
+            Object::List(list) => {
+                list.push(value);
+                true
+            }
+            Object::Range(_) | Object::String(_) | Object::Instance(_) => false,
         }
     }
 }
@@ -83,18 +229,63 @@ impl fmt::Display for Object {
                 }
                 write!(f, "]")
             }
+            Object::Range(range) => write!(f, "{range}"),
+            Object::String(string) => write!(f, "{string}"),
+            Object::Instance(instance) => {
+                write!(f, "{} {{", instance.class.name)?;
+                let mut iter = instance.fields.iter();
+                if let Some((name, value)) = iter.next() {
+                    write!(f, " {name}: {value}")?;
+                    for (name, value) in iter {
+                        write!(f, ", {name}: {value}")?;
+                    }
+                    write!(f, " ")?;
+                }
+                write!(f, "}}")
+            }
         }
     }
 }
 
-const STACK_SIZE: usize = 32;
+/// Operands of real arithmetic, if one of them is real and the other is
+/// a number.
+fn reals(l: &Value, r: &Value) -> Option<(f64, f64)> {
+    if !matches!(l, Value::Real(_)) && !matches!(r, Value::Real(_)) {
+        return None;
+    }
+    Some((l.to_real()?, r.to_real()?))
+}
+
+/// Operator hook methods return to this address, see `State::call_hook`.
+const HOOK_RETURN: u32 = u32::MAX;
+
+/// Finds operator hook method, like `add` or `eq`, of instance.
+fn hook(value: &Value, name: &str) -> Option<u32> {
+    match value {
+        Value::Object(object) => match &*object.borrow() {
+            Object::Instance(instance) => instance.class.method(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Default count of values in stack, it holds locals and temporaries
+/// of every active call.
+pub const STACK_LIMIT: usize = 1 << 20;
 
 pub struct State {
     pc: u32,
-    stack: [Value; STACK_SIZE],
+    /// Stack grows on demand up to `stack_limit` values.
+    stack: Vec<Value>,
+    stack_limit: usize,
     sp: u32,
     locals: u32,
+    frames: u32,
+    classes: HashMap<String, Rc<Class>>,
     message: Option<String>,
+    /// Integer overflow raises error instead of promotion to big integer.
+    checked: bool,
 }
 
 #[derive(Debug)]
@@ -103,18 +294,31 @@ pub enum VMError {
     StackOverflow,
     FetchOpcodeError,
     Custom,
+    /// Integer overflow or invalid operands of arithmetic operator,
+    /// message names the operands.
+    Arithmetic,
 }
 
 pub type VMResult<T = ()> = Result<T, VMError>;
 
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl State {
     pub fn new() -> Self {
         Self {
             pc: 0,
-            stack: std::array::from_fn(|_| Value::Void),
+            stack: Vec::new(),
+            stack_limit: STACK_LIMIT,
             sp: 0,
             locals: 0,
+            frames: 0,
+            classes: HashMap::new(),
             message: None,
+            checked: false,
         }
     }
 
@@ -122,8 +326,20 @@ impl State {
         self.pc = pc;
     }
 
+    /// Turns checked arithmetic on. Integer overflow, shift amount out
+    /// of `0..64` and lost bits of left shift raise `VMError::Arithmetic`.
+    pub fn set_checked(&mut self, checked: bool) {
+        self.checked = checked;
+    }
+
+    /// Sets maximal count of values in stack, deeper recursion raises
+    /// `VMError::StackOverflow`.
+    pub fn set_stack_limit(&mut self, limit: usize) {
+        self.stack_limit = limit;
+    }
+
     pub fn message(&self) -> Option<&str> {
-        self.message.as_ref().and_then(|s| Some(s.as_str()))
+        self.message.as_deref()
     }
 
     /// Pop one element from stack and forget them.
@@ -137,23 +353,31 @@ impl State {
         }
     }
 
+    /// Grows stack to hold `size` values.
+    /// Raise error if it exceeds the limit.
+    fn reserve(&mut self, size: u32) -> VMResult {
+        let size = size as usize;
+        if size > self.stack_limit {
+            return Err(VMError::StackOverflow);
+        }
+        if self.stack.len() < size {
+            self.stack.resize(size, Value::Void);
+        }
+        Ok(())
+    }
+
     /// Push value to stack.
     fn push(&mut self, value: Value) -> VMResult {
-        if self.sp >= self.stack.len() as u32 {
-            Err(VMError::StackOverflow)
-        } else {
-            self.stack[self.sp as usize] = value;
-            self.sp += 1;
-            Ok(())
-        }
+        self.reserve(self.sp + 1)?;
+        self.stack[self.sp as usize] = value;
+        self.sp += 1;
+        Ok(())
     }
 
     /// Pop value from stack.
     fn pop(&mut self) -> VMResult<Value> {
         if self.sp == 0 {
             Err(VMError::StackUnderflow)
-        } else if self.sp >= self.stack.len() as u32 {
-            Err(VMError::StackOverflow)
         } else {
             self.sp -= 1;
             Ok(self.stack[self.sp as usize].clone())
@@ -164,8 +388,6 @@ impl State {
     fn peek(&mut self) -> VMResult<Value> {
         if self.sp == 0 {
             Err(VMError::StackUnderflow)
-        } else if self.sp >= self.stack.len() as u32 {
-            Err(VMError::StackOverflow)
         } else {
             Ok(self.stack[(self.sp - 1) as usize].clone())
         }
@@ -176,73 +398,345 @@ impl State {
         Err(VMError::Custom)
     }
 
-    fn bin_ls(&mut self, l: Value, r: Value) -> VMResult<Value> {
+    fn arithmetic_error<T>(&mut self, m: String) -> VMResult<T> {
+        self.message = Some(m);
+        Err(VMError::Arithmetic)
+    }
+
+    /// Applies integer operator to `l` and `r`, `checked` is tried first
+    /// and `big` is used when the result doesn't fit into `i64`.
+    /// Returns `None` if values are not integers.
+    fn integer_operator(
+        &mut self,
+        operator: &str,
+        l: &Value,
+        r: &Value,
+        checked: fn(i64, i64) -> Option<i64>,
+        big: fn(&BigInt, &BigInt) -> BigInt,
+    ) -> Option<VMResult<Value>> {
+        if let (Value::Integer(a), Value::Integer(b)) = (l, r) {
+            match checked(*a, *b) {
+                Some(value) => return Some(Ok(Value::Integer(value))),
+                None if self.checked => {
+                    return Some(
+                        self.arithmetic_error(format!("Integer overflow in {l} {operator} {r}.")),
+                    )
+                }
+                None => (),
+            }
+        }
+        Some(Ok(Value::big(big(&l.to_big()?, &r.to_big()?))))
+    }
+
+    fn bin_ls(&mut self, module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
         dumpop!("LS");
         match (l.clone(), r.clone()) {
             (Value::Integer(l), Value::Integer(r)) => Ok(Value::Boolean(l < r)),
-            _ => self.error(format!("Unable to compare {l} and {r} values.")),
+            _ => self.less(module, &l, &r, false).map(Value::Boolean),
         }
     }
 
-    fn bin_le(&mut self, l: Value, r: Value) -> VMResult<Value> {
+    fn bin_le(&mut self, module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
         dumpop!("LE");
         match (l.clone(), r.clone()) {
             (Value::Integer(l), Value::Integer(r)) => Ok(Value::Boolean(l <= r)),
-            _ => self.error(format!("Unable to compare {l} and {r} values.")),
+            _ => self
+                .less(module, &l, &r, true)
+                .map(|greater| Value::Boolean(!greater)),
         }
     }
 
-    fn bin_gr(&mut self, l: Value, r: Value) -> VMResult<Value> {
+    fn bin_gr(&mut self, module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
         dumpop!("GR");
         match (l.clone(), r.clone()) {
             (Value::Integer(l), Value::Integer(r)) => Ok(Value::Boolean(l > r)),
-            _ => self.error(format!("Unable to compare {l} and {r} values.")),
+            _ => self.less(module, &l, &r, true).map(Value::Boolean),
+        }
+    }
+
+    fn bin_ge(&mut self, module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
+        dumpop!("GE");
+        match (l.clone(), r.clone()) {
+            (Value::Integer(l), Value::Integer(r)) => Ok(Value::Boolean(l >= r)),
+            _ => self
+                .less(module, &l, &r, false)
+                .map(|less| Value::Boolean(!less)),
+        }
+    }
+
+    fn bin_ne(&mut self, module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
+        match self.bin_eq(module, l, r)? {
+            Value::Boolean(value) => Ok(Value::Boolean(!value)),
+            value => self.error(format!("Expected bool value, found {value}.")),
         }
     }
 
-    fn bin_eq(&mut self, l: Value, r: Value) -> VMResult<Value> {
+    fn bin_eq(&mut self, module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
         dumpop!("EQ");
+        if let Some(address) = hook(&l, "eq") {
+            return self
+                .call_hook_bool(module, "eq", address, &[l, r])
+                .map(Value::Boolean);
+        }
         match (l.clone(), r.clone()) {
             (Value::Integer(l), Value::Integer(r)) => Ok(Value::Boolean(l == r)),
+            // Big integers are never equal to `i64` ones, they are demoted when fit.
+            (Value::BigInt(l), Value::BigInt(r)) => Ok(Value::Boolean(l == r)),
+            (Value::BigInt(_), Value::Integer(_)) | (Value::Integer(_), Value::BigInt(_)) => {
+                Ok(Value::Boolean(false))
+            }
+            (Value::Object(l), Value::Object(r)) => match (&*l.borrow(), &*r.borrow()) {
+                (Object::String(l), Object::String(r)) => Ok(Value::Boolean(l == r)),
+                _ => Ok(Value::Boolean(Rc::ptr_eq(&l, &r))),
+            },
             _ => self.error(format!("Unable to compare {l} and {r} values.")),
         }
     }
 
-    fn bin_add(&mut self, l: Value, r: Value) -> VMResult<Value> {
+    fn bin_add(&mut self, module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
         dumpop!("ADD");
+        if let Some(result) = self.integer_operator("+", &l, &r, i64::checked_add, |l, r| l + r) {
+            return result;
+        }
         match (l.clone(), r.clone()) {
-            (Value::Integer(l), Value::Integer(r)) => Ok(Value::Integer(l.wrapping_add(r))),
+            (l, r) if l.is_string() => {
+                let r = self.stringify(module, r)?;
+                Ok(Value::string(format!("{l}{r}")))
+            }
+            (l, r) if hook(&l, "add").is_some() => {
+                let address = hook(&l, "add").unwrap();
+                self.call_hook(module, address, &[l, r])
+            }
             (Value::Object(object), value) => {
-                {
-                    let mut object = object.borrow_mut();
-                    object.push(value);
+                if object.borrow_mut().push(value) {
+                    Ok(Value::Object(object))
+                } else {
+                    self.error(format!("Unable to addict {l} and {r} values."))
                 }
-                Ok(Value::Object(object))
             }
             _ => self.error(format!("Unable to addict {l} and {r} values.")),
         }
     }
 
-    fn bin_sub(&mut self, l: Value, r: Value) -> VMResult<Value> {
+    fn bin_sub(&mut self, _module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
         dumpop!("SUB");
-        match (l.clone(), r.clone()) {
-            (Value::Integer(l), Value::Integer(r)) => Ok(Value::Integer(l.wrapping_sub(r))),
-            _ => self.error(format!("Unable to addict {l} and {r} values.")),
+        match self.integer_operator("-", &l, &r, i64::checked_sub, |l, r| l - r) {
+            Some(result) => result,
+            None => self.error(format!("Unable to addict {l} and {r} values.")),
         }
     }
 
-    fn bin_mul(&mut self, l: Value, r: Value) -> VMResult<Value> {
+    fn bin_mul(&mut self, _module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
         dumpop!("MUL");
-        match (l.clone(), r.clone()) {
-            (Value::Integer(l), Value::Integer(r)) => Ok(Value::Integer(l.wrapping_mul(r))),
-            _ => self.error(format!("Unable to addict {l} and {r} values.")),
+        match self.integer_operator("*", &l, &r, i64::checked_mul, |l, r| l * r) {
+            Some(result) => result,
+            None => self.error(format!("Unable to addict {l} and {r} values.")),
+        }
+    }
+
+    /// Integer division truncates like `i64` one, division by zero is
+    /// an error in any mode. Integer divided by real or real by integer
+    /// is real.
+    fn bin_div(&mut self, _module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
+        dumpop!("DIV");
+        if let Some((a, b)) = reals(&l, &r) {
+            return Ok(Value::Real(a / b));
+        }
+        if matches!(r, Value::Integer(0)) && l.to_big().is_some() {
+            return self.arithmetic_error(format!("Division by zero in {l} / {r}."));
+        }
+        let quotient = |l: &BigInt, r: &BigInt| l.div_rem(r).unwrap().0;
+        match self.integer_operator("/", &l, &r, i64::checked_div, quotient) {
+            Some(result) => result,
+            None => self.error(format!("Unable to divide {l} by {r}.")),
+        }
+    }
+
+    /// Remainder has the sign of `l`.
+    fn bin_rem(&mut self, _module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
+        dumpop!("REM");
+        if let Some((a, b)) = reals(&l, &r) {
+            return Ok(Value::Real(a % b));
+        }
+        if matches!(r, Value::Integer(0)) && l.to_big().is_some() {
+            return self.arithmetic_error(format!("Division by zero in {l} % {r}."));
+        }
+        let remainder = |l: &BigInt, r: &BigInt| l.div_rem(r).unwrap().1;
+        match self.integer_operator("%", &l, &r, i64::checked_rem, remainder) {
+            Some(result) => result,
+            None => self.error(format!("Unable to divide {l} by {r}.")),
+        }
+    }
+
+    /// Shifts integer, left shift which doesn't fit into `i64` is promoted
+    /// to big integer unless checked mode is on. Negative shift amount is
+    /// an error in any mode, amount beyond `0..64` only in checked mode.
+    fn shift(&mut self, operator: &str, l: Value, r: Value) -> VMResult<Value> {
+        let left = operator == "<<";
+        if let (Value::Integer(value), Value::Integer(amount @ 0..64)) = (&l, &r) {
+            if !left {
+                return Ok(Value::Integer(value >> amount));
+            }
+            if (value << amount) >> amount == *value {
+                return Ok(Value::Integer(value << amount));
+            }
+        }
+        let (Some(value), Some(amount)) = (l.to_big(), r.to_big()) else {
+            return self.error(format!("Unable to shift {l} by {r}."));
+        };
+        if amount.is_negative() {
+            return self.arithmetic_error(format!("Negative shift amount in {l} {operator} {r}."));
+        }
+        let amount = amount
+            .to_i64()
+            .and_then(|amount| u32::try_from(amount).ok());
+        let amount = match amount {
+            Some(amount) if !self.checked || amount < 64 => amount,
+            // Right shift by any amount beyond the value gives 0 or -1.
+            None if !left && !self.checked => u32::MAX,
+            _ => {
+                return self.arithmetic_error(format!(
+                    "Shift amount is out of range in {l} {operator} {r}."
+                ))
+            }
+        };
+        if left {
+            if self.checked && matches!(l, Value::Integer(_)) {
+                return self.arithmetic_error(format!("Integer overflow in {l} {operator} {r}."));
+            }
+            Ok(Value::big(&value << amount))
+        } else {
+            Ok(Value::big(&value >> amount))
+        }
+    }
+
+    fn bin_shl(&mut self, _module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
+        dumpop!("SHL");
+        self.shift("<<", l, r)
+    }
+
+    fn bin_shr(&mut self, _module: &let_module::Module, l: Value, r: Value) -> VMResult<Value> {
+        dumpop!("SHR");
+        self.shift(">>", l, r)
+    }
+
+    fn range_bound(&mut self, value: Value) -> VMResult<Option<i64>> {
+        match value {
+            Value::Void => Ok(None),
+            Value::Integer(value) => Ok(Some(value)),
+            _ => self.error(format!("Expected integer range bound, found {value}.")),
+        }
+    }
+
+    fn bin_range(&mut self, l: Value, r: Value, inclusive: bool) -> VMResult<Value> {
+        let start = self.range_bound(l)?;
+        let end = self.range_bound(r)?;
+        if inclusive && end.is_none() {
+            return self.error("Inclusive range must have an end.".to_string());
+        }
+        Ok(Value::Object(Rc::new(RefCell::new(Object::Range(
+            Range::new(start, end, inclusive),
+        )))))
+    }
+
+    fn bin_range_exclusive(
+        &mut self,
+        _module: &let_module::Module,
+        l: Value,
+        r: Value,
+    ) -> VMResult<Value> {
+        dumpop!("RANGE");
+        self.bin_range(l, r, false)
+    }
+
+    fn bin_range_inclusive(
+        &mut self,
+        _module: &let_module::Module,
+        l: Value,
+        r: Value,
+    ) -> VMResult<Value> {
+        dumpop!("IRANGE");
+        self.bin_range(l, r, true)
+    }
+
+    /// Runs function at `address` with `args` until it returns.
+    /// Used to call operator hooks of instances from opcode handlers.
+    fn call_hook(
+        &mut self,
+        module: &let_module::Module,
+        address: u32,
+        args: &[Value],
+    ) -> VMResult<Value> {
+        let pc = self.pc;
+        self.push(Value::Address(address))?;
+        for arg in args {
+            self.push(arg.clone())?;
+        }
+        self.call(module, args.len() as u8, HOOK_RETURN)?;
+        while self.pc != HOOK_RETURN {
+            if !self.step(module)? {
+                return self.error("Program is stopped inside of operator hook.".to_string());
+            }
+            if DUMP_STACK {
+                self.dump_stack();
+                println!();
+            }
+        }
+        self.pc = pc;
+        self.pop()
+    }
+
+    fn call_hook_bool(
+        &mut self,
+        module: &let_module::Module,
+        name: &str,
+        address: u32,
+        args: &[Value],
+    ) -> VMResult<bool> {
+        match self.call_hook(module, address, args)? {
+            Value::Boolean(value) => Ok(value),
+            value => self.error(format!("Method {name} must return bool, found {value}.")),
+        }
+    }
+
+    /// Checks if `l < r`, or `r < l` if `swapped`. Instances are compared
+    /// by `lt` method of the left operand or, if it has none, of the right
+    /// one, so every comparison operator calls the same method.
+    fn less(
+        &mut self,
+        module: &let_module::Module,
+        l: &Value,
+        r: &Value,
+        swapped: bool,
+    ) -> VMResult<bool> {
+        let (a, b) = if swapped { (r, l) } else { (l, r) };
+        if let (Some(a), Some(b)) = (a.to_big(), b.to_big()) {
+            return Ok(a < b);
+        }
+        match hook(l, "lt").or_else(|| hook(r, "lt")) {
+            Some(address) => self.call_hook_bool(module, "lt", address, &[a.clone(), b.clone()]),
+            None => self.error(format!("Unable to compare {l} and {r} values.")),
+        }
+    }
+
+    /// Converts value to string, using `to_string` method of instances.
+    /// Functions are named by function table.
+    fn stringify(&mut self, module: &let_module::Module, value: Value) -> VMResult<String> {
+        if let Value::Address(address) | Value::Closure(address, ..) = value {
+            if let Some(function) = module.functions.find(address) {
+                return Ok(String::from_utf8_lossy(&function.name).into_owned());
+            }
+        }
+        match hook(&value, "to_string") {
+            Some(address) => Ok(self.call_hook(module, address, &[value])?.to_string()),
+            None => Ok(value.to_string()),
         }
     }
 
     /// Execute functor F as binary operator for this state.
-    fn binary<F>(&mut self, f: F) -> VMResult
+    fn binary<F>(&mut self, module: &let_module::Module, f: F) -> VMResult
     where
-        F: Fn(&mut Self, Value, Value) -> VMResult<Value>,
+        F: Fn(&mut Self, &let_module::Module, Value, Value) -> VMResult<Value>,
     {
         if self.sp < 2 || self.sp > self.stack.len() as u32 {
             return Err(VMError::StackOverflow);
@@ -250,28 +744,93 @@ impl State {
         self.sp -= 1;
         self.stack[(self.sp - 1) as usize] = f(
             self,
+            module,
             self.stack[(self.sp - 1) as usize].clone(),
             self.stack[(self.sp) as usize].clone(),
         )?;
         Ok(())
     }
 
-    fn index_get_list(&mut self, data: &Vec<Value>, key: Value) -> VMResult<Value> {
+    fn out_of_range<T>(&mut self, index: i64, len: usize) -> VMResult<T> {
+        self.error(format!("Index {index} out of range for length {len}."))
+    }
+
+    /// Resolves index, negative index is counted from the end.
+    fn resolve_index(&mut self, index: i64, len: usize) -> VMResult<usize> {
+        match range::resolve_index(index, len, false) {
+            Some(index) => Ok(index),
+            None => self.out_of_range(index, len),
+        }
+    }
+
+    fn slice(&mut self, range: Range, len: usize) -> VMResult<core::ops::Range<usize>> {
+        match range.slice(len) {
+            Ok(range) => Ok(range),
+            Err(range::SliceError::OutOfRange(index)) => self.out_of_range(index, len),
+            Err(range::SliceError::Reversed) => {
+                self.error(format!("Range {range} is reversed for length {len}."))
+            }
+        }
+    }
+
+    fn index_get_list(&mut self, data: &[Value], key: Value) -> VMResult<Value> {
         match key {
             Value::Integer(index) => {
-                if index >= 0 && (index as usize) < data.len() {
-                    Ok(data[index as usize].clone())
-                } else {
-                    self.error(format!("Index out of range."))
+                let index = self.resolve_index(index, data.len())?;
+                Ok(data[index].clone())
+            }
+            _ => match key.range() {
+                Some(range) => {
+                    let range = self.slice(range, data.len())?;
+                    Ok(Value::Object(Rc::new(RefCell::new(Object::List(
+                        data[range].to_vec(),
+                    )))))
                 }
+                None => self.error(format!("Can't to index list by {key}.")),
+            },
+        }
+    }
+
+    fn index_get_range(&mut self, data: Range, key: Value) -> VMResult<Value> {
+        match key {
+            Value::Integer(index) if index >= 0 => match data.get(index as usize) {
+                Some(value) => Ok(Value::Integer(value)),
+                None => self.out_of_range(index, data.len().unwrap_or(usize::MAX)),
+            },
+            Value::Integer(index) => match data.len() {
+                Some(len) => {
+                    let index = self.resolve_index(index, len)?;
+                    Ok(Value::Integer(data.get(index).unwrap()))
+                }
+                None => self.error(format!("Can't to index range {data} from the end.")),
+            },
+            _ => self.error(format!("Can't to index range by {key}.")),
+        }
+    }
+
+    fn index_get_string(&mut self, data: &str, key: Value) -> VMResult<Value> {
+        let chars: Vec<char> = data.chars().collect();
+        match key {
+            Value::Integer(index) => {
+                let index = self.resolve_index(index, chars.len())?;
+                Ok(Value::string(chars[index].to_string()))
             }
-            _ => self.error(format!("Can't to index list by {key}.")),
+            _ => match key.range() {
+                Some(range) => {
+                    let range = self.slice(range, chars.len())?;
+                    Ok(Value::string(chars[range].iter().collect()))
+                }
+                None => self.error(format!("Can't to index string by {key}.")),
+            },
         }
     }
 
     fn index_get_object(&mut self, data: &Object, key: Value) -> VMResult<Value> {
         match data {
             Object::List(list) => self.index_get_list(list, key),
+            Object::Range(range) => self.index_get_range(*range, key),
+            Object::String(string) => self.index_get_string(string, key),
+            Object::Instance(_) => self.error(format!("Can't to index {data}.")),
         }
     }
 
@@ -285,15 +844,12 @@ impl State {
         }
     }
 
-    fn index_set_list(&mut self, data: &mut Vec<Value>, key: Value, value: Value) -> VMResult {
+    fn index_set_list(&mut self, data: &mut [Value], key: Value, value: Value) -> VMResult {
         match key {
             Value::Integer(index) => {
-                if index >= 0 && (index as usize) < data.len() {
-                    data[index as usize] = value;
-                    Ok(())
-                } else {
-                    self.error(format!("Index out of range."))
-                }
+                let index = self.resolve_index(index, data.len())?;
+                data[index] = value;
+                Ok(())
             }
             _ => self.error(format!("Can't to index list by {key}.")),
         }
@@ -302,6 +858,9 @@ impl State {
     fn index_set_object(&mut self, data: &mut Object, key: Value, value: Value) -> VMResult {
         match data {
             Object::List(list) => self.index_set_list(list, key, value),
+            Object::Range(range) => self.error(format!("Can't to change range {range}.")),
+            Object::String(string) => self.error(format!("Can't to change string {string}.")),
+            Object::Instance(_) => self.error(format!("Can't to index {data}.")),
         }
     }
 
@@ -322,11 +881,11 @@ impl State {
         Ok(true)
     }
 
-    fn op_binary<F>(&mut self, f: F) -> VMResult<bool>
+    fn op_binary<F>(&mut self, module: &let_module::Module, f: F) -> VMResult<bool>
     where
-        F: Fn(&mut Self, Value, Value) -> VMResult<Value>,
+        F: Fn(&mut Self, &let_module::Module, Value, Value) -> VMResult<Value>,
     {
-        self.binary(f)?;
+        self.binary(module, f)?;
         self.pc += 1;
         Ok(true)
     }
@@ -338,6 +897,66 @@ impl State {
         Ok(true)
     }
 
+    fn op_swap(&mut self) -> VMResult<bool> {
+        dumpop!("SWAP");
+        if self.sp < 2 {
+            return Err(VMError::StackUnderflow);
+        }
+        self.stack
+            .swap((self.sp - 1) as usize, (self.sp - 2) as usize);
+        self.pc += 1;
+        Ok(true)
+    }
+
+    fn op_neg(&mut self) -> VMResult<bool> {
+        dumpop!("NEG");
+        let result = match self.pop()? {
+            Value::Integer(value) => match value.checked_neg() {
+                Some(value) => Value::Integer(value),
+                None if self.checked => {
+                    return self.arithmetic_error(format!("Integer overflow in -({value})."))
+                }
+                None => Value::big(-&BigInt::from(value)),
+            },
+            Value::BigInt(value) => Value::big(-&*value),
+            Value::Real(value) => Value::Real(-value),
+            value => return self.error(format!("Unable to negate {value} value.")),
+        };
+        self.push(result)?;
+        self.pc += 1;
+        Ok(true)
+    }
+
+    fn op_not(&mut self) -> VMResult<bool> {
+        dumpop!("NOT");
+        let result = match self.pop()? {
+            Value::Boolean(value) => Value::Boolean(!value),
+            value => return self.error(format!("Expected bool value, found {value}.")),
+        };
+        self.push(result)?;
+        self.pc += 1;
+        Ok(true)
+    }
+
+    fn op_dup(&mut self) -> VMResult<bool> {
+        dumpop!("DUP");
+        let value = self.peek()?;
+        self.push(value)?;
+        self.pc += 1;
+        Ok(true)
+    }
+
+    fn op_rot(&mut self) -> VMResult<bool> {
+        dumpop!("ROT");
+        if self.sp < 3 {
+            return Err(VMError::StackUnderflow);
+        }
+        let top = self.sp as usize;
+        self.stack[top - 3..top].rotate_right(1);
+        self.pc += 1;
+        Ok(true)
+    }
+
     fn op_list(&mut self) -> VMResult<bool> {
         dumpop!("LIST");
         self.push(Value::Object(Rc::new(RefCell::new(Object::List(
@@ -355,6 +974,251 @@ impl State {
         Ok(true)
     }
 
+    fn op_int2(&mut self, opcodes: &[u8]) -> VMResult<bool> {
+        let val = u16::from_be_bytes([
+            fetch_u8(opcodes, self.pc + 1)?,
+            fetch_u8(opcodes, self.pc + 2)?,
+        ]);
+        dumpop!("INT {val}");
+        self.push(Value::Integer(val as i64))?;
+        self.pc += 3;
+        Ok(true)
+    }
+
+    fn op_int8(&mut self, opcodes: &[u8]) -> VMResult<bool> {
+        let mut bytes = [0; 8];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = fetch_u8(opcodes, self.pc + 1 + i as u32)?;
+        }
+        let val = i64::from_be_bytes(bytes);
+        dumpop!("INT {val}");
+        self.push(Value::Integer(val))?;
+        self.pc += 9;
+        Ok(true)
+    }
+
+    fn op_bigint(&mut self, module: &let_module::Module) -> VMResult<bool> {
+        let index = fetch_u32(&module.opcodes, self.pc + 1)?;
+        let value = match module.strings.get(index) {
+            Some(bytes) => BigInt::from_be_bytes(bytes),
+            None => return self.error(format!("Unknown string constant {index}.")),
+        };
+        dumpop!("BIGINT {value}");
+        self.push(Value::big(value))?;
+        self.pc += 5;
+        Ok(true)
+    }
+
+    fn op_real(&mut self, opcodes: &[u8]) -> VMResult<bool> {
+        let mut bytes = [0; 8];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = fetch_u8(opcodes, self.pc + 1 + i as u32)?;
+        }
+        let val = f64::from_be_bytes(bytes);
+        dumpop!("REAL {val}");
+        self.push(Value::Real(val))?;
+        self.pc += 9;
+        Ok(true)
+    }
+
+    /// Fetches string constant by index operand.
+    fn fetch_string(&mut self, module: &let_module::Module) -> VMResult<String> {
+        let index = fetch_u32(&module.opcodes, self.pc + 1)?;
+        match module.strings.get(index) {
+            Some(string) => Ok(String::from_utf8_lossy(string).into()),
+            None => self.error(format!("Unknown string constant {index}.")),
+        }
+    }
+
+    fn op_str(&mut self, module: &let_module::Module) -> VMResult<bool> {
+        let string = self.fetch_string(module)?;
+        dumpop!("STR {string:?}");
+        self.push(Value::string(string))?;
+        self.pc += 5;
+        Ok(true)
+    }
+
+    fn find_class(&mut self, name: &str) -> VMResult<Rc<Class>> {
+        match self.classes.get(name) {
+            Some(class) => Ok(class.clone()),
+            None => self.error(format!("Unknown class {name}.")),
+        }
+    }
+
+    fn op_new(&mut self, module: &let_module::Module) -> VMResult<bool> {
+        let name = self.fetch_string(module)?;
+        dumpop!("NEW {name}");
+        let class = self.find_class(&name)?;
+        self.push(Value::Object(Rc::new(RefCell::new(Object::Instance(
+            Instance {
+                class,
+                fields: Vec::new(),
+            },
+        )))))?;
+        self.pc += 5;
+        Ok(true)
+    }
+
+    fn op_getf(&mut self, module: &let_module::Module) -> VMResult<bool> {
+        let name = self.fetch_string(module)?;
+        dumpop!("GETF {name}");
+        let object = self.pop()?;
+        let result = match &object {
+            Value::Object(data) => match &*data.borrow() {
+                Object::Instance(instance) => match instance.get(&name) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(format!(
+                        "Class {} has no field {name}.",
+                        instance.class.name
+                    )),
+                },
+                _ => Err(format!("Unable to get field {name} of {object}.")),
+            },
+            _ => Err(format!("Unable to get field {name} of {object}.")),
+        };
+        match result {
+            Ok(value) => self.push(value)?,
+            Err(message) => return self.error(message),
+        }
+        self.pc += 5;
+        Ok(true)
+    }
+
+    /// Type names are the ones of annotations, class names are qualified.
+    fn op_type(&mut self, module: &let_module::Module) -> VMResult<bool> {
+        let name = self.fetch_string(module)?;
+        dumpop!("TYPE {name}");
+        let value = self.peek()?;
+        let valid = match &value {
+            Value::Object(object) => match &*object.borrow() {
+                Object::Instance(instance) => instance.class.is(&name),
+                _ => value.type_name() == name,
+            },
+            _ => value.type_name() == name,
+        };
+        if !valid {
+            return self.error(format!("Expected {name}, found {}.", value.type_name()));
+        }
+        self.pc += 5;
+        Ok(true)
+    }
+
+    fn op_setf(&mut self, module: &let_module::Module) -> VMResult<bool> {
+        let name = self.fetch_string(module)?;
+        dumpop!("SETF {name}");
+        let value = self.pop()?;
+        let object = self.pop()?;
+        let done = match &object {
+            Value::Object(data) => match &mut *data.borrow_mut() {
+                Object::Instance(instance) => {
+                    instance.set(&name, value.clone());
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+        if !done {
+            return self.error(format!("Unable to set field {name} of {object}."));
+        }
+        self.push(value)?;
+        self.pc += 5;
+        Ok(true)
+    }
+
+    /// Calls method of the first argument. The slot under arguments holds
+    /// Void for dynamic dispatch or class name to call method of its parent.
+    fn op_invoke(&mut self, module: &let_module::Module) -> VMResult<bool> {
+        let name = self.fetch_string(module)?;
+        let params_count = fetch_u8(&module.opcodes, self.pc + 5)?;
+        dumpop!("INVOKE {name} {params_count}");
+        if params_count == 0 || self.sp < params_count as u32 + 1 {
+            return Err(VMError::StackUnderflow);
+        }
+        let slot = (self.sp - params_count as u32 - 1) as usize;
+        let receiver = self.stack[slot + 1].clone();
+        let class = match &receiver {
+            Value::Object(object) => match &*object.borrow() {
+                Object::Instance(instance) => Some(instance.class.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let class = match (class, self.stack[slot].clone()) {
+            (Some(class), Value::Void) => class,
+            (Some(_), current) => {
+                let current = self.find_class(&current.to_string())?;
+                match &current.parent {
+                    Some(parent) => parent.clone(),
+                    None => return self.error(format!("Class {} has no parent.", current.name)),
+                }
+            }
+            (None, _) => return self.error(format!("Unable to call method {name} of {receiver}.")),
+        };
+        match class.method(&name) {
+            Some(address) => {
+                self.stack[slot] = Value::Address(address);
+                self.call(module, params_count, self.pc + 9)
+            }
+            None if name == "init" && params_count == 1 => {
+                // Class without constructor.
+                self.sp -= 2;
+                self.push(Value::Void)?;
+                self.pc += 9;
+                Ok(true)
+            }
+            None => self.error(format!("Class {} has no method {name}.", class.name)),
+        }
+    }
+
+    /// Pops `count` values from the stack, first popped value is the last.
+    fn pop_many(&mut self, count: u8) -> VMResult<Vec<Value>> {
+        if self.sp < count as u32 {
+            return Err(VMError::StackUnderflow);
+        }
+        self.sp -= count as u32;
+        Ok(self.stack[self.sp as usize..(self.sp + count as u32) as usize].to_vec())
+    }
+
+    fn op_concat(&mut self, module: &let_module::Module) -> VMResult<bool> {
+        let count = fetch_u8(&module.opcodes, self.pc + 1)?;
+        dumpop!("CONCAT {count}");
+        let mut result = String::new();
+        for value in self.pop_many(count)? {
+            result += &self.stringify(module, value)?;
+        }
+        self.push(Value::string(result))?;
+        self.pc += 2;
+        Ok(true)
+    }
+
+    fn op_format(&mut self, module: &let_module::Module) -> VMResult<bool> {
+        let count = fetch_u8(&module.opcodes, self.pc + 1)?;
+        dumpop!("FORMAT {count}");
+        let mut args = self.pop_many(count)?;
+        for arg in args.iter_mut() {
+            if hook(arg, "to_string").is_some() {
+                *arg = Value::string(self.stringify(module, arg.clone())?);
+            }
+        }
+        let result = match args.split_first() {
+            Some((spec, args)) => match &spec {
+                Value::Object(object) => match &*object.borrow() {
+                    Object::String(spec) => format::format(spec, args),
+                    _ => Err(format!("Expected format string, found {spec}.")),
+                },
+                _ => Err(format!("Expected format string, found {spec}.")),
+            },
+            None => Err("Expected format string.".to_string()),
+        };
+        match result {
+            Ok(result) => self.push(Value::string(result))?,
+            Err(message) => return self.error(message),
+        }
+        self.pc += 2;
+        Ok(true)
+    }
+
     fn op_ptr(&mut self, opcodes: &[u8]) -> VMResult<bool> {
         let address = fetch_u32(opcodes, self.pc + 1)?;
         dumpop!("PTR {address}");
@@ -387,30 +1251,129 @@ impl State {
         Ok(true)
     }
 
-    fn op_call(&mut self, opcodes: &[u8]) -> VMResult<bool> {
-        let params_count = fetch_u8(opcodes, self.pc + 1)?;
+    fn op_jpfk(&mut self, opcodes: &[u8]) -> VMResult<bool> {
+        match self.peek()? {
+            Value::Boolean(false) => {
+                self.pc = fetch_u32(opcodes, self.pc + 1)?;
+                dumpop!("JPFK {}", self.pc);
+            }
+            Value::Boolean(true) => {
+                dumpop!("JPFK {}", fetch_u32(opcodes, self.pc + 1)?);
+                self.drop()?;
+                self.pc += 5;
+            }
+            value => return self.error(format!("Expected bool value, found {value}.")),
+        }
+        Ok(true)
+    }
+
+    fn op_jpv(&mut self, opcodes: &[u8]) -> VMResult<bool> {
+        if let Value::Void = self.peek()? {
+            self.pc = fetch_u32(opcodes, self.pc + 1)?;
+            dumpop!("JPV {}", self.pc);
+        } else {
+            dumpop!("JPV {}", fetch_u32(opcodes, self.pc + 1)?);
+            self.pc += 5;
+        }
+        Ok(true)
+    }
+
+    fn op_jpnv(&mut self, opcodes: &[u8]) -> VMResult<bool> {
+        if let Value::Void = self.peek()? {
+            dumpop!("JPNV {}", fetch_u32(opcodes, self.pc + 1)?);
+            self.drop()?;
+            self.pc += 5;
+        } else {
+            self.pc = fetch_u32(opcodes, self.pc + 1)?;
+            dumpop!("JPNV {}", self.pc);
+        }
+        Ok(true)
+    }
+
+    fn call_state(&mut self, locals: u32) -> VMResult<CallState> {
+        match self.stack[locals as usize - 1].clone() {
+            Value::CallState(state) => Ok(state),
+            value => self.error(format!("Expected CallState, found {value}")),
+        }
+    }
+
+    /// Returns locals of the frame `depth` levels up by static links.
+    fn outer_frame(&mut self, depth: u8) -> VMResult<u32> {
+        let mut locals = self.locals;
+        for _ in 0..depth {
+            if locals == 0 {
+                return self.error("Enclosing function frame not found.".to_string());
+            }
+            locals = self.call_state(locals)?.link;
+        }
+        Ok(locals)
+    }
+
+    fn frame_id(&mut self, locals: u32) -> VMResult<u32> {
+        if locals == 0 {
+            Ok(0)
+        } else {
+            Ok(self.call_state(locals)?.frame)
+        }
+    }
+
+    fn op_call(&mut self, module: &let_module::Module) -> VMResult<bool> {
+        let params_count = fetch_u8(&module.opcodes, self.pc + 1)?;
         dumpop!("CALL {params_count}");
+        self.call(module, params_count, self.pc + 2)
+    }
+
+    /// Calls function which address is stored under `params_count` arguments.
+    /// Arguments count and stack size are taken from function table.
+    fn call(
+        &mut self,
+        module: &let_module::Module,
+        params_count: u8,
+        return_pc: u32,
+    ) -> VMResult<bool> {
         if self.sp < params_count as u32 + 1 {
             return Err(VMError::StackUnderflow);
         }
         let in_stack_offset = self.sp - params_count as u32 - 1;
         let address = self.stack[in_stack_offset as usize].clone();
-        self.stack[in_stack_offset as usize] = Value::CallState(self.pc + 2, self.locals);
-        match address {
-            Value::Address(address) => self.pc = address,
+        let (address, link) = match address {
+            Value::Address(address) => (address, 0),
+            Value::Closure(address, locals, frame) => {
+                if locals >= self.sp || self.frame_id(locals)? != frame {
+                    return self.error(format!(
+                        "Local function {address} is called outside of its scope."
+                    ));
+                }
+                (address, locals)
+            }
             _ => return self.error(format!("Expected address, found {address}")),
-        }
-        self.locals = self.sp - params_count as u32;
-        let params_count_for_check = fetch_u8(opcodes, self.pc)?;
-        if params_count != params_count_for_check {
+        };
+        let function = match module.functions.find(address) {
+            Some(function) => function,
+            None => return self.error(format!("No function at address {address}.")),
+        };
+        if params_count != function.arguments {
             return self.error(format!(
-                "Expected {params_count_for_check} function call arguments, found {params_count}."
+                "Function {} expects {} arguments, found {params_count}.",
+                String::from_utf8_lossy(&function.name),
+                function.arguments
             ));
         }
-        let stack_size = fetch_u32(opcodes, self.pc + 1)?;
-        self.sp += stack_size;
-        dumpop!("Call info: parameters count: {params_count}, stack_size: {stack_size}");
-        self.pc += 5;
+        self.reserve(self.sp.saturating_add(function.stack_size))?;
+        self.frames = self.frames.wrapping_add(1);
+        self.stack[in_stack_offset as usize] = Value::CallState(CallState {
+            pc: return_pc,
+            locals: self.locals,
+            frame: self.frames,
+            link,
+        });
+        self.pc = address;
+        self.locals = self.sp - params_count as u32;
+        self.sp += function.stack_size;
+        dumpop!(
+            "Call info: parameters count: {params_count}, stack_size: {}",
+            function.stack_size
+        );
         Ok(true)
     }
 
@@ -423,16 +1386,55 @@ impl State {
         self.sp = self.locals - 1;
         let call_state = self.stack[self.sp as usize].clone();
         match call_state {
-            Value::CallState(new_pc, new_locals) => {
+            Value::CallState(state) => {
                 self.push(result)?;
-                self.pc = new_pc;
-                self.locals = new_locals;
+                self.pc = state.pc;
+                self.locals = state.locals;
             }
             _ => return self.error(format!("Expected CallState, found {call_state}")),
         }
         Ok(true)
     }
 
+    fn op_clos(&mut self, opcodes: &[u8]) -> VMResult<bool> {
+        let depth = fetch_u8(opcodes, self.pc + 1)?;
+        dumpop!("CLOS {depth}");
+        let locals = self.outer_frame(depth)?;
+        let frame = self.frame_id(locals)?;
+        match self.pop()? {
+            Value::Address(address) => self.push(Value::Closure(address, locals, frame))?,
+            value => return self.error(format!("Expected address, found {value}")),
+        }
+        self.pc += 2;
+        Ok(true)
+    }
+
+    fn op_ldu(&mut self, opcodes: &[u8]) -> VMResult<bool> {
+        let depth = fetch_u8(opcodes, self.pc + 1)?;
+        let index = fetch_u8(opcodes, self.pc + 2)?;
+        dumpop!("LDU {depth} {index}");
+        let locals = self.outer_frame(depth)?;
+        if locals + index as u32 >= self.stack.len() as u32 {
+            return Err(VMError::StackOverflow);
+        }
+        self.push(self.stack[(locals + index as u32) as usize].clone())?;
+        self.pc += 3;
+        Ok(true)
+    }
+
+    fn op_stu(&mut self, opcodes: &[u8]) -> VMResult<bool> {
+        let depth = fetch_u8(opcodes, self.pc + 1)?;
+        let index = fetch_u8(opcodes, self.pc + 2)?;
+        dumpop!("STU {depth} {index}");
+        let locals = self.outer_frame(depth)?;
+        if locals + index as u32 >= self.stack.len() as u32 {
+            return Err(VMError::StackOverflow);
+        }
+        self.stack[(locals + index as u32) as usize] = self.peek()?;
+        self.pc += 3;
+        Ok(true)
+    }
+
     fn op_ld1(&mut self, opcodes: &[u8]) -> VMResult<bool> {
         let index = fetch_u8(opcodes, self.pc + 1)?;
         dumpop!("LD {index}");
@@ -455,22 +1457,32 @@ impl State {
         Ok(true)
     }
 
-    fn op_get(&mut self) -> VMResult<bool> {
+    fn op_get(&mut self, module: &let_module::Module) -> VMResult<bool> {
         dumpop!("GET");
         let key = self.pop()?;
         let data = self.pop()?;
-        let result = self.index_get(data, key)?;
+        let result = match hook(&data, "index") {
+            Some(address) => self.call_hook(module, address, &[data, key])?,
+            None => self.index_get(data, key)?,
+        };
         self.push(result)?;
         self.pc += 1;
         Ok(true)
     }
 
-    fn op_set(&mut self) -> VMResult<bool> {
+    /// Instances are changed by `set_index` method, the counterpart of
+    /// `index` one.
+    fn op_set(&mut self, module: &let_module::Module) -> VMResult<bool> {
         dumpop!("SET");
         let value = self.pop()?;
         let key = self.pop()?;
         let data = self.pop()?;
-        self.index_set(data, key, value.clone())?;
+        match hook(&data, "set_index") {
+            Some(address) => {
+                self.call_hook(module, address, &[data, key, value.clone()])?;
+            }
+            None => self.index_set(data, key, value.clone())?,
+        }
         self.push(value)?;
         self.pc += 1;
         Ok(true)
@@ -478,29 +1490,61 @@ impl State {
 
     /// Executes one opcode.
     /// Returns Ok(false) if VM is stopped.
-    fn step(&mut self, opcodes: &[u8]) -> VMResult<bool> {
+    fn step(&mut self, module: &let_module::Module) -> VMResult<bool> {
+        let opcodes = &module.opcodes;
         let opcode = fetch_u8(opcodes, self.pc)?;
         match opcode {
             let_opcodes::DROP => self.op_drop(),
-            let_opcodes::LS => self.op_binary(Self::bin_ls),
-            let_opcodes::GR => self.op_binary(Self::bin_gr),
-            let_opcodes::EQ => self.op_binary(Self::bin_eq),
-            let_opcodes::ADD => self.op_binary(Self::bin_add),
-            let_opcodes::LE => self.op_binary(Self::bin_le),
-            let_opcodes::SUB => self.op_binary(Self::bin_sub),
-            let_opcodes::MUL => self.op_binary(Self::bin_mul),
+            let_opcodes::LS => self.op_binary(module, Self::bin_ls),
+            let_opcodes::GR => self.op_binary(module, Self::bin_gr),
+            let_opcodes::EQ => self.op_binary(module, Self::bin_eq),
+            let_opcodes::ADD => self.op_binary(module, Self::bin_add),
+            let_opcodes::LE => self.op_binary(module, Self::bin_le),
+            let_opcodes::GE => self.op_binary(module, Self::bin_ge),
+            let_opcodes::NE => self.op_binary(module, Self::bin_ne),
+            let_opcodes::SUB => self.op_binary(module, Self::bin_sub),
+            let_opcodes::MUL => self.op_binary(module, Self::bin_mul),
+            let_opcodes::DIV => self.op_binary(module, Self::bin_div),
+            let_opcodes::REM => self.op_binary(module, Self::bin_rem),
+            let_opcodes::SHL => self.op_binary(module, Self::bin_shl),
+            let_opcodes::SHR => self.op_binary(module, Self::bin_shr),
+            let_opcodes::RANGE => self.op_binary(module, Self::bin_range_exclusive),
+            let_opcodes::IRANGE => self.op_binary(module, Self::bin_range_inclusive),
             let_opcodes::VOID => self.op_void(),
+            let_opcodes::SWAP => self.op_swap(),
+            let_opcodes::DUP => self.op_dup(),
+            let_opcodes::NEG => self.op_neg(),
+            let_opcodes::NOT => self.op_not(),
+            let_opcodes::ROT => self.op_rot(),
             let_opcodes::LIST => self.op_list(),
             let_opcodes::INT1 => self.op_int1(opcodes),
+            let_opcodes::INT2 => self.op_int2(opcodes),
+            let_opcodes::INT8 => self.op_int8(opcodes),
+            let_opcodes::BIGINT => self.op_bigint(module),
+            let_opcodes::REAL => self.op_real(opcodes),
+            let_opcodes::STR => self.op_str(module),
+            let_opcodes::NEW => self.op_new(module),
+            let_opcodes::GETF => self.op_getf(module),
+            let_opcodes::SETF => self.op_setf(module),
+            let_opcodes::TYPE => self.op_type(module),
+            let_opcodes::INVOKE => self.op_invoke(module),
+            let_opcodes::CONCAT => self.op_concat(module),
+            let_opcodes::FORMAT => self.op_format(module),
             let_opcodes::PTR => self.op_ptr(opcodes),
             let_opcodes::JPF => self.op_jpf(opcodes),
             let_opcodes::JP => self.op_jp(opcodes),
-            let_opcodes::CALL => self.op_call(opcodes),
+            let_opcodes::JPV => self.op_jpv(opcodes),
+            let_opcodes::JPFK => self.op_jpfk(opcodes),
+            let_opcodes::JPNV => self.op_jpnv(opcodes),
+            let_opcodes::CALL => self.op_call(module),
             let_opcodes::RET => self.op_ret(),
             let_opcodes::LD1 => self.op_ld1(opcodes),
+            let_opcodes::CLOS => self.op_clos(opcodes),
+            let_opcodes::LDU => self.op_ldu(opcodes),
+            let_opcodes::STU => self.op_stu(opcodes),
             let_opcodes::ST1 => self.op_st1(opcodes),
-            let_opcodes::GET => self.op_get(),
-            let_opcodes::SET => self.op_set(),
+            let_opcodes::GET => self.op_get(module),
+            let_opcodes::SET => self.op_set(module),
             _ => self.error(format!("Unknown opcode 0x{opcode:02X}")),
         }
     }
@@ -515,8 +1559,87 @@ impl State {
         println!();
     }
 
-    pub fn run(&mut self, opcodes: &[u8]) -> VMResult<Value> {
-        while self.step(opcodes)? {
+    /// Loads class with its ancestors, `path` holds classes being loaded.
+    fn load_class(
+        &mut self,
+        module: &let_module::Module,
+        name: &[u8],
+        path: &mut Vec<Box<[u8]>>,
+    ) -> VMResult<Rc<Class>> {
+        let class_name = String::from_utf8_lossy(name).into_owned();
+        if let Some(class) = self.classes.get(&class_name) {
+            return Ok(class.clone());
+        }
+        if path.iter().any(|loading| loading.as_ref() == name) {
+            return self.error(format!("Class {class_name} inherits itself."));
+        }
+        let descriptor = match module.classes.get(name) {
+            Some(descriptor) => descriptor,
+            None => return self.error(format!("Unknown class {class_name}.")),
+        };
+
+        path.push(descriptor.name.clone());
+        let parent = match &descriptor.parent {
+            Some(parent) => Some(self.load_class(module, parent, path)?),
+            None => None,
+        };
+        path.pop();
+
+        let mut methods = HashMap::new();
+        for method in descriptor.methods.iter() {
+            let mut label = Vec::from(name);
+            label.push(b'.');
+            label.extend(method.iter());
+            match module.labels.get(&label) {
+                Some(address) => {
+                    methods.insert(String::from_utf8_lossy(method).into_owned(), address);
+                }
+                None => {
+                    return self.error(format!(
+                        "Method {} of class {class_name} not found.",
+                        String::from_utf8_lossy(method)
+                    ))
+                }
+            }
+        }
+
+        let class = Rc::new(Class {
+            name: class_name.clone(),
+            parent,
+            methods,
+        });
+        self.classes.insert(class_name, class.clone());
+        Ok(class)
+    }
+
+    fn load_classes(&mut self, module: &let_module::Module) -> VMResult {
+        for descriptor in module.classes.iter() {
+            self.load_class(module, &descriptor.name, &mut Vec::new())?;
+        }
+        Ok(())
+    }
+
+    /// Functions of active calls, the innermost first. Global code
+    /// is not a function, so it is skipped.
+    pub fn trace<'a>(&self, module: &'a let_module::Module) -> Vec<&'a let_module::Function> {
+        let mut trace = Vec::new();
+        let mut pc = self.pc;
+        let mut locals = self.locals;
+        loop {
+            trace.extend(module.functions.containing(pc));
+            match self.stack.get(locals.wrapping_sub(1) as usize) {
+                Some(Value::CallState(state)) if locals != 0 => {
+                    pc = state.pc;
+                    locals = state.locals;
+                }
+                _ => return trace,
+            }
+        }
+    }
+
+    pub fn run(&mut self, module: &let_module::Module) -> VMResult<Value> {
+        self.load_classes(module)?;
+        while self.step(module)? {
             if DUMP_STACK {
                 self.dump_stack();
                 println!();
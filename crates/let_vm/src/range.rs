@@ -0,0 +1,189 @@
+use core::fmt;
+
+/// Range of integers created by `a..b` and `a..=b` operators.
+/// Both bounds are optional, so `..5` and `1..` are valid ranges.
+#[derive(Clone, Copy)]
+pub struct Range {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub inclusive: bool,
+}
+
+impl Range {
+    pub fn new(start: Option<i64>, end: Option<i64>, inclusive: bool) -> Self {
+        Self {
+            start,
+            end,
+            inclusive,
+        }
+    }
+
+    /// Lazy iterator over range values.
+    /// Range without end is infinite.
+    pub fn iter(&self) -> RangeIter {
+        RangeIter {
+            current: Some(self.start.unwrap_or(0)),
+            end: self.exclusive_end(),
+        }
+    }
+
+    /// Exclusive end, `None` if range has no end.
+    fn exclusive_end(&self) -> Option<i64> {
+        match self.end {
+            Some(end) if self.inclusive => end.checked_add(1),
+            end => end,
+        }
+    }
+
+    /// Count of values in range, `None` if range has no end.
+    pub fn len(&self) -> Option<usize> {
+        self.exclusive_end()
+            .map(|end| end.saturating_sub(self.start.unwrap_or(0)).max(0) as usize)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Gets value by index without iterating the range.
+    pub fn get(&self, index: usize) -> Option<i64> {
+        if let Some(len) = self.len() {
+            if index >= len {
+                return None;
+            }
+        }
+        self.start.unwrap_or(0).checked_add(index as i64)
+    }
+
+    /// Resolves range bounds for a sequence with `len` elements.
+    /// Negative bounds are counted from the end of sequence.
+    pub fn slice(&self, len: usize) -> Result<core::ops::Range<usize>, SliceError> {
+        let start = match self.start {
+            Some(start) => resolve_index(start, len, true).ok_or(SliceError::OutOfRange(start))?,
+            None => 0,
+        };
+        let end = match self.end {
+            Some(end) => {
+                let index =
+                    resolve_index(end, len, !self.inclusive).ok_or(SliceError::OutOfRange(end))?;
+                if self.inclusive {
+                    index + 1
+                } else {
+                    index
+                }
+            }
+            None => len,
+        };
+        if start > end {
+            Err(SliceError::Reversed)
+        } else {
+            Ok(start..end)
+        }
+    }
+}
+
+/// Error of `Range::slice`.
+#[derive(Debug, PartialEq)]
+pub enum SliceError {
+    /// Bound is outside of sequence.
+    OutOfRange(i64),
+    /// Start is after end, like `2..1`.
+    Reversed,
+}
+
+/// Converts possibly negative index to offset in sequence with `len` elements.
+/// If `allow_end` is set, index equal to `len` is valid too.
+pub fn resolve_index(index: i64, len: usize, allow_end: bool) -> Option<usize> {
+    let index = if index < 0 {
+        (len as i64).checked_add(index)?
+    } else {
+        index
+    };
+    if index < 0 {
+        return None;
+    }
+    let index = index as usize;
+    if index < len || (allow_end && index == len) {
+        Some(index)
+    } else {
+        None
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(start) = self.start {
+            write!(f, "{start}")?;
+        }
+        write!(f, "{}", if self.inclusive { "..=" } else { ".." })?;
+        if let Some(end) = self.end {
+            write!(f, "{end}")?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoIterator for Range {
+    type Item = i64;
+    type IntoIter = RangeIter;
+
+    fn into_iter(self) -> RangeIter {
+        self.iter()
+    }
+}
+
+pub struct RangeIter {
+    /// Next value, `None` after `i64::MAX`.
+    current: Option<i64>,
+    end: Option<i64>,
+}
+
+impl Iterator for RangeIter {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.current?;
+        if self.end.is_some_and(|end| value >= end) {
+            return None;
+        }
+        self.current = value.checked_add(1);
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Range, SliceError};
+
+    #[test]
+    fn iteration() {
+        let values: Vec<i64> = Range::new(Some(-1), Some(2), false).into_iter().collect();
+        assert_eq!(values, [-1, 0, 1]);
+        let values: Vec<i64> = Range::new(None, Some(2), true).into_iter().collect();
+        assert_eq!(values, [0, 1, 2]);
+        assert_eq!(Range::new(Some(3), Some(1), false).iter().next(), None);
+    }
+
+    #[test]
+    fn range_without_end_is_infinite() {
+        let mut iter = Range::new(Some(5), None, false).into_iter();
+        assert_eq!(iter.nth(1_000_000), Some(1_000_005));
+        let last = Range::new(Some(i64::MAX - 1), None, false).iter().last();
+        assert_eq!(last, Some(i64::MAX));
+    }
+
+    #[test]
+    fn slices() {
+        assert_eq!(Range::new(Some(1), None, false).slice(3), Ok(1..3));
+        assert_eq!(Range::new(Some(-2), Some(-1), true).slice(3), Ok(1..3));
+        assert_eq!(Range::new(Some(2), Some(2), false).slice(3), Ok(2..2));
+        assert_eq!(
+            Range::new(Some(2), Some(1), false).slice(3),
+            Err(SliceError::Reversed)
+        );
+        assert_eq!(
+            Range::new(Some(0), Some(3), true).slice(3),
+            Err(SliceError::OutOfRange(3))
+        );
+    }
+}
@@ -1,60 +1,180 @@
-use std::fmt::Write;
-use std::io::Seek;
 use std::path::Path;
 
-mod line;
+fn parse(
+    file: &let_result::SourceFile,
+    emitter: &mut let_emitter::Emitter,
+    checks: let_codegen::Checks,
+    format: let_result::ErrorFormat,
+) -> let_result::Result {
+    let path = file.path();
+    let module_name = Path::new(path)
+        .file_stem()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .as_bytes();
+    let mut parser = let_parser::Parser::new(file.text().iter().copied());
+    let ast = parser.parse();
+    let mut diagnostics = parser.errors().to_vec();
+    // Incomplete tree is not generated, ill-typed one is, so errors of
+    // both passes are reported. Module with errors is never emitted.
+    if diagnostics.is_empty() {
+        let mut checker = let_codegen::TypeChecker::new(module_name);
+        let types = checker.check(&ast);
+        diagnostics.extend_from_slice(checker.errors());
+        let mut generator = let_codegen::Generator::new(module_name, emitter)
+            .with_checks(checks)
+            .with_types(types);
+        generator.generate(&ast);
+        diagnostics.extend_from_slice(generator.diagnostics());
+    }
 
-fn parse<R>(path: &str, file: R, emitter: &mut let_emitter::Emitter) -> let_result::Result
-where
-    R: std::io::Read + std::io::Seek,
-{
-    let mut iter = read_iter::ReadIter::new(file, 1024);
-    let module_name = Path::new(path).file_stem().unwrap().to_str().unwrap().as_bytes();
-    let mut parser = let_parser::Parser::new(&mut iter, module_name, emitter);
-    if let Err(error) = parser.parse() {
-        let range = parser.range();
-        iter.seek(std::io::SeekFrom::Start(0))?;
-        let info = line::create(&mut iter, range.start);
-        let mut buffer = String::new();
-        writeln!(buffer, "File \"{path}\", line: {}:", info.number).unwrap();
-        line::print_line(&mut iter, info.start, &mut buffer);
-        line::mark_range(info.start, range, &mut buffer);
-        writeln!(buffer, "Compile error: {}", error).unwrap();
-        return Err(let_result::Error::Custom(Box::new(buffer)));
+    let mut buffer = String::new();
+    for diagnostic in &diagnostics {
+        format.render(diagnostic, file, &mut buffer);
     }
-    if let Some(error) = iter.get_error() {
-        return let_result::raise!("Error reading file \"{path}\", IOError: {error}");
+    eprint!("{buffer}");
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    if errors > 0 {
+        return let_result::raise!(
+            let_result::codes::COMPILATION_FAILED,
+            "Unable to compile \"{path}\" due to {errors} error{}.",
+            if errors == 1 { "" } else { "s" }
+        );
     }
     Ok(())
 }
 
-fn compile(input_path: &str, output_path: &str) -> let_result::Result {
+/// Parses `--warn <check>` and `--error <check>` options.
+/// Returns false if argument is not a check option.
+fn check_option(
+    arg: &str,
+    level: &mut Option<let_codegen::Level>,
+    checks: &mut let_codegen::Checks,
+) -> Result<bool, String> {
+    if let Some(level) = level.take() {
+        if checks.set(arg, level) {
+            return Ok(true);
+        }
+        return Err(format!("Unknown check \"{arg}\"."));
+    }
+    match arg {
+        "-W" | "--warn" => *level = Some(let_codegen::Level::Warning),
+        "-E" | "--error" => *level = Some(let_codegen::Level::Error),
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn read_interface(path: &str) -> let_result::Result<let_module::Interface> {
+    match std::fs::File::open(path) {
+        Ok(file) => let_module::Interface::read(std::io::BufReader::new(file)),
+        Err(error) => let_result::raise!(
+            let_result::codes::IO,
+            "Unable to open interface \"{path}\", error: {error}"
+        ),
+    }
+}
+
+/// Interface of module is written next to it, like `main.lmi`
+/// for `main.lm`.
+fn write_interface(interface: &let_module::Interface, output_path: &str) -> let_result::Result {
+    let path = Path::new(output_path).with_extension("lmi");
+    match std::fs::File::create(&path) {
+        Ok(file) => interface.write(std::io::BufWriter::new(file)),
+        Err(error) => let_result::raise!(
+            let_result::codes::IO,
+            "Unable to create file {path:?}, error: {error}."
+        ),
+    }
+}
+
+/// Calls of modules with `interfaces` are checked, their sources are
+/// not needed.
+fn compile(
+    sources: &mut let_result::SourceMap,
+    input_path: &str,
+    output_path: &str,
+    interfaces: &[let_module::Interface],
+    checks: let_codegen::Checks,
+    format: let_result::ErrorFormat,
+) -> let_result::Result {
     let start = std::time::Instant::now();
-    match std::fs::File::open(input_path) {
-        Ok(file) => {
-            let mut emitter = let_emitter::Emitter::new();
-            parse(input_path, file, &mut emitter)?;
-            emitter.resolve()?;
-            emitter.finish(output_path)?;
-            println!(
-                "Compiled \"{input_path}\", time: {} seconds",
-                (std::time::Instant::now() - start).as_secs_f64()
-            );
-            Ok(())
+    let file = sources.load(input_path)?;
+    let mut emitter = let_emitter::Emitter::new();
+    emitter.set_source(input_path)?;
+    for interface in interfaces {
+        emitter.import(interface.clone());
+    }
+    parse(sources.get(file), &mut emitter, checks, format)?;
+    emitter.resolve()?;
+    // Other modules are linked later, links to this one must be resolved.
+    let undefined = emitter.undefined();
+    if !undefined.is_empty() {
+        for diagnostic in &undefined {
+            eprint!("{}", format.render_in(diagnostic, sources));
         }
-        Err(error) => let_result::raise!("Unable to open file \"{input_path}\", error: {error}"),
+        return let_result::raise!(
+            let_result::codes::COMPILATION_FAILED,
+            "Unable to compile \"{input_path}\" due to {} undefined symbol{}.",
+            undefined.len(),
+            if undefined.len() == 1 { "" } else { "s" }
+        );
     }
+    write_interface(&emitter.interface(), output_path)?;
+    emitter.finish(output_path)?;
+    println!(
+        "Compiled \"{input_path}\", time: {} seconds",
+        (std::time::Instant::now() - start).as_secs_f64()
+    );
+    Ok(())
 }
 
 fn main() -> std::process::ExitCode {
     println!("Let Compiler");
     let mut input_path: Option<String> = None;
+    let mut checks = let_codegen::Checks::default();
+    let mut level = None;
+    let mut format = let_result::ErrorFormat::default();
+    let mut sources = let_result::SourceMap::new();
+    let mut interfaces = Vec::new();
     for arg in std::env::args().skip(1) {
+        if let Some(path) = arg.strip_prefix("--interface=") {
+            match read_interface(path) {
+                Ok(interface) => interfaces.push(interface),
+                Err(error) => {
+                    eprint!("{}", format.render_plain(&error.into_diagnostic()));
+                    return std::process::ExitCode::FAILURE;
+                }
+            }
+            continue;
+        }
+        if let Some(name) = arg.strip_prefix("--error-format=") {
+            match let_result::ErrorFormat::parse(name) {
+                Some(value) => format = value,
+                None => {
+                    eprintln!("Unknown error format \"{name}\".");
+                    return std::process::ExitCode::FAILURE;
+                }
+            }
+            continue;
+        }
+        match check_option(&arg, &mut level, &mut checks) {
+            Ok(true) => continue,
+            Ok(false) => (),
+            Err(error) => {
+                eprintln!("{error}");
+                return std::process::ExitCode::FAILURE;
+            }
+        }
         if let Some(input_path) = input_path.take() {
-            match compile(&input_path, &arg) {
+            match compile(&mut sources, &input_path, &arg, &interfaces, checks, format) {
                 Ok(_) => (),
                 Err(error) => {
-                    eprintln!("{error}");
+                    eprint!(
+                        "{}",
+                        format.render_in(&error.into_diagnostic(), &mut sources)
+                    );
                     return std::process::ExitCode::FAILURE;
                 }
             }
@@ -1,62 +1,150 @@
-use std::fmt::Write;
-use std::io::Seek;
 use std::path::Path;
 
-mod line;
-
-fn parse<R>(path: &str, file: R, emitter: &mut let_emitter::Emitter) -> let_result::Result
-where
-    R: std::io::Read + std::io::Seek,
-{
-    let mut iter = read_iter::ReadIter::new(file, 1024);
+fn parse(
+    file: &let_result::SourceFile,
+    emitter: &mut let_emitter::Emitter,
+    checks: let_codegen::Checks,
+    format: let_result::ErrorFormat,
+) -> let_result::Result {
+    let path = file.path();
     let module_name = Path::new(path)
         .file_stem()
         .unwrap()
         .to_str()
         .unwrap()
         .as_bytes();
-    let mut parser = let_parser::Parser::new(&mut iter, module_name, emitter);
-    if let Err(error) = parser.parse() {
-        let range = parser.range();
-        iter.seek(std::io::SeekFrom::Start(0))?;
-        let info = line::create(&mut iter, range.start);
-        let mut buffer = String::new();
-        writeln!(buffer, "File \"{path}\", line: {}:", info.number).unwrap();
-        line::print_line(&mut iter, info.start, &mut buffer);
-        line::mark_range(info.start, range, &mut buffer);
-        writeln!(buffer, "Compile error: {}", error).unwrap();
-        return Err(let_result::Error::Custom(Box::new(buffer)));
+    let mut parser = let_parser::Parser::new(file.text().iter().copied());
+    let ast = parser.parse();
+    let mut diagnostics = parser.errors().to_vec();
+    // Incomplete tree is not generated, ill-typed one is, so errors of
+    // both passes are reported. Module with errors is never emitted.
+    if diagnostics.is_empty() {
+        let mut checker = let_codegen::TypeChecker::new(module_name);
+        let types = checker.check(&ast);
+        diagnostics.extend_from_slice(checker.errors());
+        let mut generator = let_codegen::Generator::new(module_name, emitter)
+            .with_checks(checks)
+            .with_types(types);
+        generator.generate(&ast);
+        diagnostics.extend_from_slice(generator.diagnostics());
+    }
+
+    let mut buffer = String::new();
+    for diagnostic in &diagnostics {
+        format.render(diagnostic, file, &mut buffer);
     }
-    if let Some(error) = iter.get_error() {
-        return let_result::raise!("Error reading file \"{path}\", IOError: {error}");
+    eprint!("{buffer}");
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    if errors > 0 {
+        return let_result::raise!(
+            let_result::codes::COMPILATION_FAILED,
+            "Unable to compile \"{path}\" due to {errors} error{}.",
+            if errors == 1 { "" } else { "s" }
+        );
     }
     Ok(())
 }
 
-fn compile(input_path: &str, emitter: &mut let_emitter::Emitter) -> let_result::Result {
-    match std::fs::File::open(input_path) {
-        Ok(file) => {
-            parse(input_path, file, emitter)?;
-            emitter.resolve()
+/// Parses `--warn <check>` and `--error <check>` options.
+/// Returns false if argument is not a check option.
+fn check_option(
+    arg: &str,
+    level: &mut Option<let_codegen::Level>,
+    checks: &mut let_codegen::Checks,
+) -> Result<bool, String> {
+    if let Some(level) = level.take() {
+        if checks.set(arg, level) {
+            return Ok(true);
         }
-        Err(error) => let_result::raise!("Unable to open file \"{input_path}\", error: {error}"),
+        return Err(format!("Unknown check \"{arg}\"."));
     }
+    match arg {
+        "-W" | "--warn" => *level = Some(let_codegen::Level::Warning),
+        "-E" | "--error" => *level = Some(let_codegen::Level::Error),
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn compile(
+    sources: &mut let_result::SourceMap,
+    input_path: &str,
+    emitter: &mut let_emitter::Emitter,
+    checks: let_codegen::Checks,
+    format: let_result::ErrorFormat,
+) -> let_result::Result {
+    let file = sources.load(input_path)?;
+    emitter.set_source(input_path)?;
+    parse(sources.get(file), emitter, checks, format)?;
+    emitter.resolve()
 }
 
 fn main() -> std::process::ExitCode {
     println!("Let Compiler");
     let mut emitter = let_emitter::Emitter::new();
+    let mut checks = let_codegen::Checks::default();
+    let mut level = None;
+    let mut format = let_result::ErrorFormat::default();
+    let mut sources = let_result::SourceMap::new();
+    let mut checked = false;
+    let mut stack_limit = let_vm::STACK_LIMIT;
     for arg in std::env::args().skip(1) {
-        match compile(&arg, &mut emitter) {
-            Ok(_) => (),
+        if arg == "--checked" {
+            checked = true;
+            continue;
+        }
+        if let Some(value) = arg.strip_prefix("--stack-limit=") {
+            match value.parse() {
+                Ok(value) => stack_limit = value,
+                Err(_) => {
+                    eprintln!("Invalid stack limit \"{value}\".");
+                    return std::process::ExitCode::FAILURE;
+                }
+            }
+            continue;
+        }
+        if let Some(name) = arg.strip_prefix("--error-format=") {
+            match let_result::ErrorFormat::parse(name) {
+                Some(value) => format = value,
+                None => {
+                    eprintln!("Unknown error format \"{name}\".");
+                    return std::process::ExitCode::FAILURE;
+                }
+            }
+            continue;
+        }
+        match check_option(&arg, &mut level, &mut checks) {
+            Ok(true) => continue,
+            Ok(false) => (),
             Err(error) => {
                 eprintln!("{error}");
                 return std::process::ExitCode::FAILURE;
             }
         }
+        match compile(&mut sources, &arg, &mut emitter, checks, format) {
+            Ok(_) => (),
+            Err(error) => {
+                eprint!(
+                    "{}",
+                    format.render_in(&error.into_diagnostic(), &mut sources)
+                );
+                return std::process::ExitCode::FAILURE;
+            }
+        }
+    }
+
+    // All modules are compiled, so every symbol must be defined.
+    let undefined = emitter.check_undefined();
+    for diagnostic in &undefined {
+        eprint!("{}", format.render_in(diagnostic, &mut sources));
+    }
+    if !undefined.is_empty() {
+        return std::process::ExitCode::FAILURE;
     }
 
     let mut state = let_vm::State::new();
+    state.set_checked(checked);
+    state.set_stack_limit(stack_limit);
 
     let module = emitter.into_module();
 
@@ -66,16 +154,23 @@ fn main() -> std::process::ExitCode {
         panic!("Unable to find 'main' module")
     }
 
-    match state.run(&module.opcodes) {
+    match state.run(&module) {
         Ok(result) => {
             println!("{}", result);
         }
-        Err(error) => match error {
-            let_vm::VMError::StackUnderflow => println!("Stack underflow."),
-            let_vm::VMError::StackOverflow => println!("Stack overflow."),
-            let_vm::VMError::FetchOpcodeError => println!("Fetch opcode error."),
-            let_vm::VMError::Custom => println!("{}", state.message().unwrap()),
-        },
+        Err(error) => {
+            match error {
+                let_vm::VMError::StackUnderflow => println!("Stack underflow."),
+                let_vm::VMError::StackOverflow => println!("Stack overflow."),
+                let_vm::VMError::FetchOpcodeError => println!("Fetch opcode error."),
+                let_vm::VMError::Custom | let_vm::VMError::Arithmetic => {
+                    println!("{}", state.message().unwrap())
+                }
+            }
+            for function in state.trace(&module) {
+                println!("    {}", function.describe(&module.strings, &mut sources));
+            }
+        }
     }
     std::process::ExitCode::SUCCESS
 }
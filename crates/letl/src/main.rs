@@ -21,20 +21,52 @@ impl Linker {
         Ok(())
     }
 
-    fn finish(self, path: &str) -> let_result::Result {
-        if let Some(current) = self.0 {
-            current.write(std::fs::File::create(path)?)?;
-            Ok(())
+    /// Partially linked library may refer to modules linked later,
+    /// if `allow_undefined` is set. Links to linked modules must be
+    /// resolved anyway.
+    fn finish(self, path: &str, allow_undefined: bool) -> let_result::Result {
+        let current = match self.0 {
+            Some(current) => current,
+            None => {
+                return let_result::raise!(
+                    let_result::codes::NO_INPUT,
+                    "Zero input files provided."
+                )
+            }
+        };
+        let undefined = if allow_undefined {
+            current.undefined()
         } else {
-            let_result::raise!("Zero input files provided.")
+            current.check_undefined()
+        };
+        if !undefined.is_empty() {
+            for diagnostic in &undefined {
+                report(diagnostic);
+            }
+            return let_result::raise!(
+                let_result::codes::UNDEFINED_SYMBOL,
+                "Unable to link \"{path}\" due to {} undefined symbol{}.",
+                undefined.len(),
+                if undefined.len() == 1 { "" } else { "s" }
+            );
         }
+        current.write(std::fs::File::create(path)?)?;
+        Ok(())
     }
 }
 
+/// Link errors may refer to source files of modules.
+fn report(diagnostic: &let_result::Diagnostic) {
+    let format = let_result::ErrorFormat::Human;
+    let mut sources = let_result::SourceMap::new();
+    eprint!("{}", format.render_in(diagnostic, &mut sources));
+}
+
 fn main() -> std::process::ExitCode {
     println!("Let Linker");
     let mut output_path = None;
     let mut waiting_output = false;
+    let mut allow_undefined = false;
     let mut linker = Linker::new();
     for arg in std::env::args().skip(1) {
         if arg == "-o" || arg == "--output" {
@@ -46,6 +78,10 @@ fn main() -> std::process::ExitCode {
                 return std::process::ExitCode::FAILURE;
             }
         }
+        if arg == "--allow-undefined" {
+            allow_undefined = true;
+            continue;
+        }
         if waiting_output {
             waiting_output = false;
             output_path = Some(arg);
@@ -55,7 +91,7 @@ fn main() -> std::process::ExitCode {
             Ok(mut file) => match linker.link(&mut file) {
                 Ok(_) => (),
                 Err(error) => {
-                    eprintln!("{error}");
+                    report(&error.into_diagnostic());
                     return std::process::ExitCode::FAILURE;
                 }
             },
@@ -66,10 +102,10 @@ fn main() -> std::process::ExitCode {
         }
     }
     if let Some(output_path) = output_path {
-        match linker.finish(&output_path) {
+        match linker.finish(&output_path, allow_undefined) {
             Ok(_) => (),
             Err(error) => {
-                eprintln!("{error}");
+                report(&error.into_diagnostic());
                 return std::process::ExitCode::FAILURE;
             }
         }
@@ -1,44 +1,199 @@
-use std::fs::File;
+use std::{collections::HashMap, fs::File};
 
-fn run<R>(read: &mut R) -> let_result::Result
+fn string(module: &let_module::Module, index: u32) -> String {
+    match module.strings.get(index) {
+        Some(value) => format!("{:?}", String::from_utf8_lossy(value)),
+        None => "?".to_string(),
+    }
+}
+
+/// Comment of 4 bytes operand, it names string or function.
+fn comment(module: &let_module::Module, opcode: u8, operand: u32) -> Option<String> {
+    match opcode {
+        let_opcodes::STR
+        | let_opcodes::NEW
+        | let_opcodes::GETF
+        | let_opcodes::SETF
+        | let_opcodes::TYPE => Some(string(module, operand)),
+        let_opcodes::PTR => module
+            .functions
+            .find(operand)
+            .map(|function| String::from_utf8_lossy(&function.name).into_owned()),
+        _ => None,
+    }
+}
+
+/// Prints code of module, functions are named by function table and
+/// global code by labels.
+fn disassemble(module: &let_module::Module) -> let_result::Result {
+    let labels: HashMap<u32, &[u8]> = module
+        .labels
+        .iter()
+        .map(|(name, address)| (address, name))
+        .collect();
+    let mut pc = 0;
+    while pc < module.opcodes.len() {
+        let address = pc as u32;
+        if let Some(function) = module.functions.find(address) {
+            println!(
+                "{}: arguments: {}, locals: {}",
+                String::from_utf8_lossy(&function.name),
+                function.arguments,
+                function.stack_size
+            );
+        } else if let Some(label) = labels.get(&address) {
+            println!("{}:", String::from_utf8_lossy(label));
+        }
+        let opcode = module.opcodes[pc];
+        let size = let_opcodes::operand_size(opcode);
+        let (name, operand) = match (
+            let_opcodes::name(opcode),
+            module.opcodes.get(pc + 1..pc + 1 + size),
+        ) {
+            (Some(name), Some(operand)) => (name, operand),
+            _ => {
+                return let_result::raise!(
+                    let_result::codes::CORRUPTED_MODULE,
+                    "Invalid opcode 0x{opcode:02X} at {address}."
+                )
+            }
+        };
+        print!("    {address:08} {name}");
+        match operand {
+            [] => (),
+            [value] => print!(" {value}"),
+            [high, low] => print!(" {}", u16::from_be_bytes([*high, *low])),
+            &[a, b, c, d] => {
+                let value = u32::from_be_bytes([a, b, c, d]);
+                print!(" {value}");
+                if let Some(comment) = comment(module, opcode, value) {
+                    print!(" ; {comment}");
+                }
+            }
+            _ => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(operand);
+                match opcode {
+                    let_opcodes::REAL => print!(" {}", f64::from_be_bytes(bytes)),
+                    let_opcodes::INVOKE => {
+                        let index = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                        print!(" {index} {} ; {}", bytes[4], string(module, index));
+                    }
+                    _ => print!(" {}", u64::from_be_bytes(bytes)),
+                }
+            }
+        }
+        println!();
+        pc += 1 + size;
+    }
+    Ok(())
+}
+
+fn run<R>(read: &mut R, checked: bool, stack_limit: usize) -> let_result::Result
 where
     R: std::io::Read,
 {
     let module = let_module::Module::read(read)?;
+    // Partially linked module would jump to address 0.
+    let undefined = module.check_undefined();
+    if !undefined.is_empty() {
+        // Undefined symbols are located in source files of modules.
+        let format = let_result::ErrorFormat::Human;
+        let mut sources = let_result::SourceMap::new();
+        for diagnostic in &undefined {
+            eprint!("{}", format.render_in(diagnostic, &mut sources));
+        }
+        return let_result::raise!(
+            let_result::codes::UNDEFINED_SYMBOL,
+            "Module has {} undefined symbol{}.",
+            undefined.len(),
+            if undefined.len() == 1 { "" } else { "s" }
+        );
+    }
 
     let mut state = let_vm::State::new();
+    state.set_checked(checked);
+    state.set_stack_limit(stack_limit);
 
     if let Some(pc) = module.labels.get(b"main") {
         state.set_pc(pc);
     } else {
-        return let_result::raise!("Unable to find \"main\" module.");
+        return let_result::raise!(
+            let_result::codes::NO_MAIN,
+            "Unable to find \"main\" module."
+        );
     }
 
-    match state.run(&module.opcodes) {
+    match state.run(&module) {
         Ok(result) => {
             println!("{}", result);
             Ok(())
         }
-        Err(error) => match error {
-            let_vm::VMError::StackUnderflow => let_result::raise!("Stack underflow."),
-            let_vm::VMError::StackOverflow => let_result::raise!("Stack overflow."),
-            let_vm::VMError::FetchOpcodeError => let_result::raise!("Fetch opcode error."),
-            let_vm::VMError::Custom => let_result::raise!("{}", state.message().unwrap()),
-        },
+        Err(error) => {
+            let message = match error {
+                let_vm::VMError::StackUnderflow => "Stack underflow.".to_string(),
+                let_vm::VMError::StackOverflow => "Stack overflow.".to_string(),
+                let_vm::VMError::FetchOpcodeError => "Fetch opcode error.".to_string(),
+                let_vm::VMError::Custom | let_vm::VMError::Arithmetic => {
+                    state.message().unwrap().to_string()
+                }
+            };
+            let mut diagnostic = let_result::Diagnostic::error(let_result::codes::RUNTIME, message);
+            let mut sources = let_result::SourceMap::new();
+            for function in state.trace(&module) {
+                diagnostic = diagnostic.with_note(function.describe(&module.strings, &mut sources));
+            }
+            Err(diagnostic.into())
+        }
     }
 }
 
-fn run_file(path: &str) -> let_result::Result {
-    run(&mut File::open(path)?)
+fn run_file(
+    path: &str,
+    checked: bool,
+    stack_limit: usize,
+    disassembly: bool,
+) -> let_result::Result {
+    if disassembly {
+        return disassemble(&let_module::Module::read(&mut File::open(path)?)?);
+    }
+    run(&mut File::open(path)?, checked, stack_limit)
 }
 
 fn main() -> std::process::ExitCode {
     println!("Let Runtime");
+    let mut checked = false;
+    let mut stack_limit = let_vm::STACK_LIMIT;
+    let mut disassembly = false;
     for arg in std::env::args().skip(1) {
-        match run_file(arg.as_str()) {
+        if arg == "--checked" {
+            checked = true;
+            continue;
+        }
+        if let Some(value) = arg.strip_prefix("--stack-limit=") {
+            match value.parse() {
+                Ok(value) => stack_limit = value,
+                Err(_) => {
+                    eprintln!("Invalid stack limit \"{value}\".");
+                    return std::process::ExitCode::FAILURE;
+                }
+            }
+            continue;
+        }
+        if arg == "--disassemble" {
+            disassembly = true;
+            continue;
+        }
+        match run_file(arg.as_str(), checked, stack_limit, disassembly) {
             Ok(_) => (),
             Err(error) => {
-                eprintln!("{error}");
+                // Undefined symbols are located in source files of modules.
+                let format = let_result::ErrorFormat::Human;
+                let mut sources = let_result::SourceMap::new();
+                eprint!(
+                    "{}",
+                    format.render_in(&error.into_diagnostic(), &mut sources)
+                );
                 return std::process::ExitCode::FAILURE;
             }
         }
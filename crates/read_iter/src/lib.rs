@@ -55,7 +55,7 @@ where
             match try_to_fill_the_buffer(&mut self.read, &mut self.buffer) {
                 Ok(0) => None,
                 Ok(size) => {
-                    self.offset -= self.buffer_size;
+                    self.offset = 1;
                     self.buffer_size = size;
                     self.buffer.first().cloned()
                 }
@@ -78,8 +78,29 @@ where
                 self.buffer_size = 0;
                 self.offset = 0;
                 self.read.seek(pos)
-            },
+            }
             _ => panic!("ReadIter supports only SeekFrom::Start type."),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ReadIter;
+
+    #[test]
+    fn refilled_buffer_is_read_once() {
+        let data: Vec<u8> = (0..10).collect();
+        let iter = ReadIter::new(std::io::Cursor::new(data.clone()), 3);
+        assert_eq!(iter.collect::<Vec<_>>(), data);
+    }
+
+    #[test]
+    fn seek_restarts_reading() {
+        use std::io::Seek;
+        let mut iter = ReadIter::new(std::io::Cursor::new(vec![1, 2, 3, 4]), 2);
+        assert_eq!(iter.next(), Some(1));
+        iter.seek(std::io::SeekFrom::Start(1)).unwrap();
+        assert_eq!(iter.collect::<Vec<_>>(), vec![2, 3, 4]);
+    }
+}